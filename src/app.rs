@@ -0,0 +1,151 @@
+use crate::redact;
+use chrono::{DateTime, TimeDelta, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::header::AUTHORIZATION;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::Path;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// How long before its expiry an installation token is replaced, so that a
+/// request never goes out with a token about to lapse.
+const REFRESH_MARGIN: TimeDelta = TimeDelta::minutes(5);
+
+/// Authenticates as a GitHub App installation. Installation tokens last an
+/// hour; a new one is minted whenever the current one is about to expire.
+pub struct AppAuth {
+    app_id: u64,
+    key: EncodingKey,
+    /// Repository used to look up the installation when no ID was given,
+    /// or just the organization.
+    owner: String,
+    repo: Option<String>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    installation_id: Option<u64>,
+    token: Option<InstallationToken>,
+}
+
+#[derive(Serialize)]
+struct Claims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+#[derive(Deserialize)]
+struct Installation {
+    id: u64,
+}
+
+#[derive(Deserialize, Clone)]
+struct InstallationToken {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+impl AppAuth {
+    pub fn new(
+        app_id: u64,
+        key_path: &Path,
+        installation_id: Option<u64>,
+        owner: &str,
+        repo: Option<&str>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let pem = std::fs::read(key_path)
+            .map_err(|err| format!("can't read app key {}: {}", key_path.display(), err))?;
+        let key = EncodingKey::from_rsa_pem(&pem)
+            .map_err(|err| format!("invalid app key {}: {}", key_path.display(), err))?;
+        Ok(AppAuth {
+            app_id,
+            key,
+            owner: owner.to_string(),
+            repo: repo.map(str::to_string),
+            state: Mutex::new(State {
+                installation_id,
+                token: None,
+            }),
+        })
+    }
+
+    /// A valid installation token, minting a new one if there is none yet or
+    /// the current one is about to expire.
+    pub async fn token(
+        &self,
+        client: &reqwest::Client,
+        api_url: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut state = self.state.lock().await;
+        if let Some(token) = &state.token {
+            if token.expires_at - Utc::now() > REFRESH_MARGIN {
+                return Ok(token.token.clone());
+            }
+        }
+        let jwt = self.jwt()?;
+        redact::register(&jwt);
+        let installation_id = match state.installation_id {
+            Some(id) => id,
+            None => {
+                let url = match &self.repo {
+                    Some(repo) => format!("{}/repos/{}/{}/installation", api_url, self.owner, repo),
+                    None => format!("{}/orgs/{}/installation", api_url, self.owner),
+                };
+                let installation: Installation = app_request(client.get(url), &jwt).await?;
+                *state.installation_id.insert(installation.id)
+            }
+        };
+        let url = format!(
+            "{}/app/installations/{}/access_tokens",
+            api_url, installation_id
+        );
+        let token: InstallationToken = app_request(client.post(url), &jwt).await?;
+        redact::register(&token.token);
+        debug!(
+            "new token for installation {}, expiring at {}",
+            installation_id, token.expires_at
+        );
+        state.token = Some(token.clone());
+        Ok(token.token)
+    }
+
+    /// A JWT signed with the app's private key. Its issue time is backdated
+    /// a minute to allow for clock drift, and GitHub rejects lifetimes over
+    /// ten minutes.
+    fn jwt(&self) -> Result<String, Box<dyn Error>> {
+        let now = Utc::now();
+        let claims = Claims {
+            iat: (now - TimeDelta::minutes(1)).timestamp(),
+            exp: (now + TimeDelta::minutes(9)).timestamp(),
+            iss: self.app_id.to_string(),
+        };
+        jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.key)
+            .map_err(|err| format!("can't sign a JWT with the app key: {}", err).into())
+    }
+}
+
+/// Sends a request authenticated as the app itself rather than one of its
+/// installations.
+async fn app_request<T: serde::de::DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    jwt: &str,
+) -> Result<T, Box<dyn Error>> {
+    let response = request
+        .header(AUTHORIZATION, format!("Bearer {}", jwt))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let url = response.url().clone();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "GitHub App authentication failed: {} for {}: {}",
+            status, url, body
+        )
+        .into());
+    }
+    Ok(response.json().await?)
+}
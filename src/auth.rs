@@ -0,0 +1,261 @@
+use crate::{check, device, redact, ConnectionArgs};
+use clap::Subcommand;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Environment variables checked for a token, in order, when no token file
+/// is given.
+const TOKEN_VARIABLES: [&str; 2] = ["GITHUB_TOKEN", "GH_TOKEN"];
+
+/// Service name tokens are stored under in the OS keyring, one entry per host.
+const KEYRING_SERVICE: &str = "pr-commits";
+
+/// `pr-commits auth` actions.
+#[derive(Subcommand, Debug)]
+pub enum AuthCommand {
+    /// Store a token in the OS keyring, read from stdin, prompted for, or
+    /// authorized in the browser
+    Login {
+        /// GitHub host the token is for
+        #[arg(long, default_value = "github.com")]
+        host: String,
+        /// Authorize in the browser through the OAuth device flow instead
+        #[arg(long)]
+        web: bool,
+        /// Client ID of the OAuth app to authorize with --web
+        #[arg(long, env = "PR_COMMITS_CLIENT_ID", required_if_eq("web", "true"))]
+        client_id: Option<String>,
+        /// OAuth scopes to request with --web
+        #[arg(long, default_value = "repo", requires = "web")]
+        scopes: String,
+    },
+    /// Remove the token stored in the OS keyring
+    Logout {
+        /// GitHub host whose token is removed
+        #[arg(long, default_value = "github.com")]
+        host: String,
+    },
+    /// Check that the credentials work and can read a repository's PRs
+    Check {
+        /// GitHub repository owner
+        #[arg(short, long)]
+        owner: String,
+        /// GitHub repository name
+        #[arg(short, long)]
+        repo: String,
+        #[command(flatten)]
+        connection: Box<ConnectionArgs>,
+    },
+    /// Show which token would be used and where it comes from
+    Status {
+        /// GitHub host to look up a token for
+        #[arg(long, default_value = "github.com")]
+        host: String,
+    },
+}
+
+/// Where a token was found.
+pub enum Source {
+    Env(&'static str),
+    Keyring,
+    Gh,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Env(variable) => write!(f, "${}", variable),
+            Source::Keyring => write!(f, "the OS keyring"),
+            Source::Gh => write!(f, "the gh CLI"),
+        }
+    }
+}
+
+/// Finds the GitHub tokens for `host`: every non-empty line of the files in
+/// `token_paths`, or else the single token [`find`] turns up. They are all
+/// registered with [`redact`] so no error message can give them away.
+pub fn tokens(token_paths: &[PathBuf], host: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let tokens = find_tokens(token_paths, host)?;
+    for token in &tokens {
+        redact::register(token);
+    }
+    Ok(tokens)
+}
+
+fn find_tokens(token_paths: &[PathBuf], host: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    if token_paths.is_empty() {
+        return match find(host) {
+            Some((token, _)) => Ok(vec![token]),
+            None => Err(
+                "no GitHub token found: pass --token-path, set GITHUB_TOKEN or GH_TOKEN, \
+                 run `pr-commits auth login`, or log in with `gh auth login`"
+                    .into(),
+            ),
+        };
+    }
+    let mut tokens = Vec::new();
+    for path in token_paths {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("can't read token file {}: {}", path.display(), err))?;
+        let before = tokens.len();
+        tokens.extend(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        );
+        if tokens.len() == before {
+            return Err(format!("no token in {}", path.display()).into());
+        }
+    }
+    Ok(tokens)
+}
+
+/// Looks for a token in order: the first of [`TOKEN_VARIABLES`] that is set
+/// and not empty, the OS keyring, and finally the gh CLI's login.
+fn find(host: &str) -> Option<(String, Source)> {
+    for variable in TOKEN_VARIABLES {
+        if let Ok(token) = std::env::var(variable) {
+            if !token.trim().is_empty() {
+                return Some((token.trim().to_string(), Source::Env(variable)));
+            }
+        }
+    }
+    if let Some(token) = keyring_token(host) {
+        return Some((token, Source::Keyring));
+    }
+    gh_token(host).map(|token| (token, Source::Gh))
+}
+
+/// The web host that goes with a REST API root, as gh names its logins:
+/// github.com for api.github.com, otherwise the API's own host.
+pub fn host(api_url: &str) -> String {
+    let host = reqwest::Url::parse(api_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    match host.as_str() {
+        "api.github.com" => "github.com".to_string(),
+        _ => host,
+    }
+}
+
+/// Runs a `pr-commits auth` subcommand.
+pub async fn run(command: &AuthCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        AuthCommand::Login {
+            host,
+            web,
+            client_id,
+            scopes,
+        } => {
+            let token = match (web, client_id) {
+                (true, Some(client_id)) => device::login(host, client_id, scopes).await?,
+                _ => read_token()?,
+            };
+            redact::register(&token);
+            if token.is_empty() {
+                return Err("no token given".into());
+            }
+            keyring_entry(host)?
+                .set_password(&token)
+                .map_err(|err| format!("can't store the token in the OS keyring: {}", err))?;
+            println!("Stored the token for {} in the OS keyring", host);
+        }
+        AuthCommand::Logout { host } => match keyring_entry(host)?.delete_credential() {
+            Ok(()) => println!("Removed the token for {} from the OS keyring", host),
+            Err(keyring::Error::NoEntry) => println!("No token for {} in the OS keyring", host),
+            Err(err) => {
+                return Err(format!("can't remove the token from the OS keyring: {}", err).into())
+            }
+        },
+        AuthCommand::Check {
+            owner,
+            repo,
+            connection,
+        } => {
+            let github = connection.client(owner, Some(repo))?;
+            check::run(&github, owner, repo).await?;
+        }
+        AuthCommand::Status { host } => match find(host) {
+            Some((token, source)) => {
+                println!("Token for {} from {}: {}", host, source, mask(&token))
+            }
+            None => println!("No token found for {}", host),
+        },
+    }
+    Ok(())
+}
+
+/// Reads a token piped to stdin, or prompts for one without echoing it.
+fn read_token() -> Result<String, Box<dyn Error>> {
+    if std::io::stdin().is_terminal() {
+        return Ok(rpassword::prompt_password("GitHub token: ")?
+            .trim()
+            .to_string());
+    }
+    let mut token = String::new();
+    std::io::stdin().read_to_string(&mut token)?;
+    Ok(token.trim().to_string())
+}
+
+/// Shows just enough of a token to tell tokens apart.
+fn mask(token: &str) -> String {
+    let prefix: String = token.chars().take(4).collect();
+    format!(
+        "{}{}",
+        prefix,
+        "*".repeat(token.chars().count().saturating_sub(4).min(16))
+    )
+}
+
+fn keyring_entry(host: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, host)
+}
+
+/// The token `pr-commits auth login` stored for `host`. A missing or
+/// unreachable keyring just means there is no token there.
+fn keyring_token(host: &str) -> Option<String> {
+    keyring_entry(host).ok()?.get_password().ok()
+}
+
+/// The token gh is logged in with for `host`. `gh auth token` knows about
+/// tokens kept in the system keyring; without gh on the PATH, its
+/// `hosts.yml` is read directly, which only holds tokens stored in plain text.
+fn gh_token(host: &str) -> Option<String> {
+    let output = Command::new("gh")
+        .args(["auth", "token", "--hostname", host])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) => {
+            let token = String::from_utf8(output.stdout).ok()?.trim().to_string();
+            (output.status.success() && !token.is_empty()).then_some(token)
+        }
+        Err(_) => gh_hosts_token(host),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct GhHost {
+    oauth_token: Option<String>,
+}
+
+fn gh_hosts_token(host: &str) -> Option<String> {
+    let dir = match std::env::var_os("GH_CONFIG_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::home_dir()?.join(".config").join("gh"),
+    };
+    let contents = std::fs::read_to_string(dir.join("hosts.yml")).ok()?;
+    let mut hosts: HashMap<String, GhHost> = serde_yaml::from_str(&contents).ok()?;
+    hosts
+        .remove(host)?
+        .oauth_token
+        .filter(|token| !token.is_empty())
+}
@@ -0,0 +1,101 @@
+use crate::cherry_pick::{self, Pick};
+use crate::github::{GithubClient, NewPull};
+use crate::{PrReport, Repository};
+use std::error::Error;
+
+/// Where a backport goes.
+pub struct Backport<'a> {
+    /// The branch the PRs are backported onto, such as `release/1.2`.
+    pub onto: &'a str,
+    /// The local branch the cherry-picks are made on.
+    pub branch: &'a str,
+    /// The remote `onto` is fetched from and `branch` pushed to.
+    pub remote: &'a str,
+    /// Push the branch and open a PR of it against `onto`.
+    pub open_pr: bool,
+    /// Only print the git commands.
+    pub dry_run: bool,
+}
+
+/// The branch a backport is made on unless named: `backport-12-13-to-release-1.2`.
+pub fn default_branch(reports: &[PrReport], onto: &str) -> String {
+    let numbers: Vec<String> = reports
+        .iter()
+        .map(|report| report.number.to_string())
+        .collect();
+    format!(
+        "backport-{}-to-{}",
+        numbers.join("-"),
+        onto.replace('/', "-")
+    )
+}
+
+/// Cherry-picks the commits of each PR, in the order given, onto a new
+/// branch off `onto`, then pushes it and opens a PR if asked to.
+pub async fn run(
+    github: &GithubClient,
+    repository: &Repository,
+    reports: &[PrReport],
+    backport: &Backport<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let picks: Vec<Pick> = reports.iter().map(Pick::new).collect();
+    let words = |words: &[&str]| -> Vec<String> { words.iter().map(|w| w.to_string()).collect() };
+    let start = [
+        words(&["fetch", backport.remote, backport.onto]),
+        words(&["checkout", "-b", backport.branch, "FETCH_HEAD"]),
+    ];
+    let push = words(&["push", "-u", backport.remote, backport.branch]);
+
+    if backport.dry_run {
+        let picking = picks.iter().flat_map(Pick::commands);
+        for command in start.into_iter().chain(picking) {
+            println!("{}", cherry_pick::command_line(&command));
+        }
+        if backport.open_pr {
+            println!("{}", cherry_pick::command_line(&push));
+            println!(
+                "(then a PR of {} against {} would be opened)",
+                backport.branch, backport.onto
+            );
+        }
+        return Ok(());
+    }
+
+    for command in &start {
+        cherry_pick::git(command)?;
+    }
+    cherry_pick::cherry_pick(&picks)?;
+    if !backport.open_pr {
+        eprintln!(
+            "Backported onto {}; push it and open a PR against {}",
+            backport.branch, backport.onto
+        );
+        return Ok(());
+    }
+    cherry_pick::git(&push)?;
+
+    let prs: Vec<String> = reports
+        .iter()
+        .map(|report| format!("#{}", report.number))
+        .collect();
+    let title = match reports {
+        [report] => format!("[{}] {}", backport.onto, report.pull.title),
+        _ => format!("[{}] Backport {}", backport.onto, prs.join(", ")),
+    };
+    let body = format!("Backport of {} to `{}`.", prs.join(", "), backport.onto);
+    let Repository { owner, name } = repository;
+    let pull = github
+        .create_pull(
+            owner,
+            name,
+            &NewPull {
+                title: &title,
+                head: backport.branch,
+                base: backport.onto,
+                body: &body,
+            },
+        )
+        .await?;
+    println!("{}", pull.html_url);
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// A response stored on disk so that later runs can revalidate it with
+/// `If-None-Match` instead of downloading it again, or serve it as-is with
+/// `--offline`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Entry {
+    pub url: String,
+    pub etag: Option<String>,
+    /// The response's `Link` header, so pagination works from the cache.
+    pub link: Option<String>,
+    pub body: String,
+    /// RFC 3339 timestamp of when the response was downloaded.
+    pub fetched_at: String,
+}
+
+/// A directory of cached API responses, one JSON file per URL.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Opens the cache in the platform cache directory (`$XDG_CACHE_HOME`
+    /// or `~/.cache` on Linux). Returns `None` if there is no such directory.
+    pub fn open() -> Option<Cache> {
+        Some(Cache {
+            dir: dirs::cache_dir()?.join("pr-commits").join("http"),
+        })
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        let digest = Sha256::digest(url.as_bytes());
+        let name: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.dir.join(format!("{}.json", name))
+    }
+
+    pub fn get(&self, url: &str) -> Option<Entry> {
+        let contents = std::fs::read_to_string(self.path(url)).ok()?;
+        let entry: Entry = serde_json::from_str(&contents).ok()?;
+        // Guard against hash collisions, however unlikely.
+        (entry.url == url).then_some(entry)
+    }
+
+    /// Stores `entry`. The cache is best-effort, so failures are ignored.
+    pub fn put(&self, entry: &Entry) {
+        let Ok(contents) = serde_json::to_string(entry) else {
+            return;
+        };
+        if std::fs::create_dir_all(&self.dir).is_ok() {
+            let _ = std::fs::write(self.path(&entry.url), contents);
+        }
+    }
+
+    /// Every readable entry in the cache. Files that aren't entries, e.g.
+    /// from an older version, are skipped.
+    fn entries(&self) -> Result<Vec<StoredEntry>, Box<dyn Error>> {
+        let dir = match std::fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut entries = Vec::new();
+        for file in dir {
+            let path = file?.path();
+            let Ok(contents) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if let Ok(entry) = serde_json::from_str(&contents) {
+                entries.push(StoredEntry {
+                    path,
+                    size: contents.len() as u64,
+                    entry,
+                });
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Runs a `pr-commits cache` subcommand.
+pub fn run(command: &CacheCommand) -> Result<(), Box<dyn Error>> {
+    let cache = Cache::open().ok_or("no cache directory on this platform")?;
+    match command {
+        CacheCommand::Status => status(&cache),
+        CacheCommand::Clear { repo } => remove(&cache, |entry| entry.matches(repo.as_deref())),
+        CacheCommand::Prune { older_than, repo } => {
+            let cutoff = Utc::now() - TimeDelta::days(i64::from(*older_than));
+            remove(&cache, |entry| {
+                entry.matches(repo.as_deref())
+                    && entry
+                        .fetched_at()
+                        .is_none_or(|fetched_at| fetched_at < cutoff)
+            })
+        }
+    }
+}
+
+/// What `cache status` reports for each repository.
+#[derive(Default)]
+struct RepoStats {
+    responses: usize,
+    size: u64,
+    oldest: Option<DateTime<Utc>>,
+    newest: Option<DateTime<Utc>>,
+}
+
+fn status(cache: &Cache) -> Result<(), Box<dyn Error>> {
+    let entries = cache.entries()?;
+    let total: u64 = entries.iter().map(|entry| entry.size).sum();
+    println!("Cache directory: {}", cache.dir.display());
+    println!("{} responses, {}", entries.len(), human_size(total));
+
+    let mut repos: BTreeMap<String, RepoStats> = BTreeMap::new();
+    for entry in &entries {
+        let repo = entry.repo().unwrap_or_else(|| "(other)".to_string());
+        let stats = repos.entry(repo).or_default();
+        stats.responses += 1;
+        stats.size += entry.size;
+        if let Some(fetched_at) = entry.fetched_at() {
+            stats.oldest = Some(
+                stats
+                    .oldest
+                    .map_or(fetched_at, |oldest| oldest.min(fetched_at)),
+            );
+            stats.newest = Some(
+                stats
+                    .newest
+                    .map_or(fetched_at, |newest| newest.max(fetched_at)),
+            );
+        }
+    }
+    for (repo, stats) in repos {
+        let fetched = match (stats.oldest, stats.newest) {
+            (Some(oldest), Some(newest)) => format!(
+                ", fetched {} to {}",
+                oldest.format("%Y-%m-%d %H:%M"),
+                newest.format("%Y-%m-%d %H:%M")
+            ),
+            _ => String::new(),
+        };
+        println!(
+            "  {}: {} responses, {}{}",
+            repo,
+            stats.responses,
+            human_size(stats.size),
+            fetched
+        );
+    }
+    Ok(())
+}
+
+fn remove(cache: &Cache, select: impl Fn(&StoredEntry) -> bool) -> Result<(), Box<dyn Error>> {
+    let mut removed = 0;
+    let mut freed = 0;
+    for entry in cache.entries()?.into_iter().filter(|entry| select(entry)) {
+        std::fs::remove_file(&entry.path)?;
+        removed += 1;
+        freed += entry.size;
+    }
+    println!("Removed {} responses, {}", removed, human_size(freed));
+    Ok(())
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// `pr-commits cache` actions.
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    /// Show where the cache lives, how much disk it uses and when it was filled
+    Status,
+    /// Delete every cached response, or only those of one repository
+    Clear {
+        /// Only clear responses for this repository
+        #[arg(long, value_name = "OWNER/REPO")]
+        repo: Option<String>,
+    },
+    /// Delete responses fetched more than a number of days ago
+    Prune {
+        /// Age in days past which a response is deleted
+        #[arg(long, value_name = "DAYS", default_value_t = 30)]
+        older_than: u32,
+        /// Only prune responses for this repository
+        #[arg(long, value_name = "OWNER/REPO")]
+        repo: Option<String>,
+    },
+}
+
+/// A cache file along with what it holds.
+struct StoredEntry {
+    path: PathBuf,
+    size: u64,
+    entry: Entry,
+}
+
+impl StoredEntry {
+    /// The `owner/repo` the response belongs to.
+    fn repo(&self) -> Option<String> {
+        let (_, rest) = self.entry.url.split_once("/repos/")?;
+        let mut segments = rest.split('/');
+        Some(format!("{}/{}", segments.next()?, segments.next()?))
+    }
+
+    fn fetched_at(&self) -> Option<DateTime<Utc>> {
+        let fetched_at = DateTime::parse_from_rfc3339(&self.entry.fetched_at).ok()?;
+        Some(fetched_at.with_timezone(&Utc))
+    }
+
+    fn matches(&self, repo: Option<&str>) -> bool {
+        repo.is_none_or(|repo| {
+            self.repo()
+                .is_some_and(|own| own.eq_ignore_ascii_case(repo))
+        })
+    }
+}
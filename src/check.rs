@@ -0,0 +1,94 @@
+use crate::github::GithubClient;
+use crate::ratelimit;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::error::Error;
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct RateLimits {
+    resources: Resources,
+}
+
+#[derive(Deserialize)]
+struct Resources {
+    core: RateLimit,
+    graphql: Option<RateLimit>,
+}
+
+#[derive(Deserialize)]
+struct RateLimit {
+    limit: u64,
+    remaining: u64,
+    reset: i64,
+}
+
+/// Reports who the credentials authenticate as, what they may do, and
+/// whether they can read the PRs of `owner/repo`. Fails if they can't.
+pub async fn run(github: &GithubClient, owner: &str, repo: &str) -> Result<(), Box<dyn Error>> {
+    let response = github.get_response("/user").await?;
+    let scopes = response
+        .headers()
+        .get("x-oauth-scopes")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    match response.status() {
+        status if status.is_success() => {
+            let user: User = response.json().await?;
+            println!("Authenticated as {}", user.login);
+        }
+        // Installation tokens have no user behind them.
+        StatusCode::FORBIDDEN => println!("Authenticated as a GitHub App installation"),
+        StatusCode::UNAUTHORIZED => {
+            return Err("the token was rejected; it may be expired or revoked".into())
+        }
+        status => return Err(format!("GET /user failed: {}", status).into()),
+    }
+    match scopes.as_deref() {
+        Some("") => println!("Token scopes: none"),
+        Some(scopes) => println!("Token scopes: {}", scopes),
+        // Fine-grained and installation tokens carry permissions instead.
+        None => println!("Token scopes: not reported (fine-grained or app token)"),
+    }
+
+    let limits: RateLimits = github.get_response("/rate_limit").await?.json().await?;
+    let describe = |name: &str, limit: &RateLimit| {
+        let reset = chrono::DateTime::from_timestamp(limit.reset, 0)
+            .map(ratelimit::describe_reset)
+            .unwrap_or_default();
+        println!(
+            "{} rate limit: {}/{} left, resets at {}",
+            name, limit.remaining, limit.limit, reset
+        );
+    };
+    describe("REST", &limits.resources.core);
+    if let Some(graphql) = &limits.resources.graphql {
+        describe("GraphQL", graphql);
+    }
+
+    let response = github
+        .get_response(&format!("/repos/{}/{}/pulls?per_page=1", owner, repo))
+        .await?;
+    match response.status() {
+        status if status.is_success() => {
+            println!("Can read the pull requests of {}/{}", owner, repo);
+            Ok(())
+        }
+        // GitHub answers 404 rather than 403 for private repos the token
+        // can't see.
+        StatusCode::NOT_FOUND | StatusCode::FORBIDDEN => Err(format!(
+            "can't read the pull requests of {}/{}: it doesn't exist or the token has no access",
+            owner, repo
+        )
+        .into()),
+        status => Err(format!(
+            "can't read the pull requests of {}/{}: {}",
+            owner, repo, status
+        )
+        .into()),
+    }
+}
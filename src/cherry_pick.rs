@@ -0,0 +1,171 @@
+use crate::output::Renderer;
+use crate::PrReport;
+use std::error::Error;
+use std::io::Write;
+use std::process::Command;
+
+/// The commits of a PR to cherry-pick, and where to fetch them from.
+pub struct Pick {
+    pub number: u32,
+    pub title: String,
+    /// URL of the PR's repository, which serves `pull/N/head` whatever fork
+    /// the PR came from.
+    pub remote: String,
+    /// Oldest first, without merge commits, which only bring the base
+    /// branch in.
+    pub shas: Vec<String>,
+}
+
+impl Pick {
+    pub fn new(report: &PrReport) -> Pick {
+        let mut shas: Vec<String> = Vec::new();
+        for commit in &report.commits {
+            // Expanded co-authors follow their commit with the same SHA.
+            if commit.is_merge() || shas.last() == Some(&commit.sha) {
+                continue;
+            }
+            shas.push(commit.sha.clone());
+        }
+        let url = &report.pull.html_url;
+        let remote = url
+            .strip_suffix(&format!("/pull/{}", report.number))
+            .unwrap_or(url);
+        Pick {
+            number: report.number,
+            title: report.pull.title.clone(),
+            remote: remote.to_string(),
+            shas,
+        }
+    }
+
+    /// The `git` arguments fetching the PR's commits and cherry-picking
+    /// them, with `-x` recording where each one came from.
+    pub fn commands(&self) -> Vec<Vec<String>> {
+        if self.shas.is_empty() {
+            return Vec::new();
+        }
+        let fetch = vec![
+            "fetch".to_string(),
+            self.remote.clone(),
+            format!("pull/{}/head", self.number),
+        ];
+        let cherry_pick = ["cherry-pick", "-x"]
+            .map(str::to_string)
+            .into_iter()
+            .chain(self.shas.iter().cloned())
+            .collect();
+        vec![fetch, cherry_pick]
+    }
+}
+
+/// Quotes `word` for a POSIX shell when it needs it.
+fn quote(word: &str) -> String {
+    let plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@%+=,".contains(c));
+    if plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// A shell script checking out `branch` and cherry-picking the commits of
+/// each PR onto it, stopping at the first conflict.
+pub fn script(branch: &str, picks: &[Pick]) -> String {
+    let mut script = String::from("#!/bin/sh\nset -e\n\n");
+    script.push_str(&format!("git checkout {}\n", quote(branch)));
+    for pick in picks {
+        script.push_str(&format!("\n# PR #{} - {}\n", pick.number, pick.title));
+        if pick.shas.is_empty() {
+            script.push_str("# (no commits to cherry-pick)\n");
+        }
+        for command in pick.commands() {
+            script.push_str(&command_line(&command));
+            script.push('\n');
+        }
+    }
+    script
+}
+
+/// `git` with `args`, quoted for a shell.
+pub fn command_line(args: &[String]) -> String {
+    let words: Vec<String> = args.iter().map(|word| quote(word)).collect();
+    format!("git {}", words.join(" "))
+}
+
+/// Checks out `branch` and cherry-picks the commits of each PR onto it,
+/// in the current directory. A conflict leaves the cherry-pick in progress
+/// for the user to resolve.
+pub fn apply(branch: &str, picks: &[Pick]) -> Result<(), Box<dyn Error>> {
+    git(&["checkout".to_string(), branch.to_string()])?;
+    cherry_pick(picks)
+}
+
+/// Fetches the commits of each PR and cherry-picks them onto the branch
+/// checked out.
+pub fn cherry_pick(picks: &[Pick]) -> Result<(), Box<dyn Error>> {
+    for pick in picks {
+        for command in pick.commands() {
+            git(&command).map_err(|err| match command[0].as_str() {
+                "cherry-pick" => format!(
+                    "{} while cherry-picking PR #{}; resolve the conflicts and run \
+                     `git cherry-pick --continue`, or `git cherry-pick --abort`",
+                    err, pick.number
+                )
+                .into(),
+                _ => err,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs `git` with `args` in the current directory, its output going
+/// straight to the terminal.
+pub fn git(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let status = Command::new("git")
+        .args(args)
+        .status()
+        .map_err(|err| format!("can't run git: {}", err))?;
+    if !status.success() {
+        return Err(format!("`{}` failed", command_line(args)).into());
+    }
+    Ok(())
+}
+
+/// Collects the commits of every PR, then writes the script or, with
+/// `apply`, runs it.
+pub fn renderer(branch: String, apply: bool, out: Box<dyn Write>) -> Box<dyn Renderer> {
+    Box::new(CherryPickRenderer {
+        out,
+        branch,
+        apply,
+        picks: Vec::new(),
+    })
+}
+
+struct CherryPickRenderer {
+    out: Box<dyn Write>,
+    branch: String,
+    apply: bool,
+    picks: Vec<Pick>,
+}
+
+impl Renderer for CherryPickRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        self.picks.push(Pick::new(report));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.apply {
+            return apply(&self.branch, &self.picks);
+        }
+        self.out
+            .write_all(script(&self.branch, &self.picks).as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use crate::github::{CheckRun, StatusContext};
+use serde::Serialize;
+
+/// Conclusions that mean a check run failed.
+const FAILED: [&str; 4] = ["failure", "timed_out", "cancelled", "action_required"];
+
+/// Where the check runs and commit statuses of a commit stand.
+#[derive(Serialize, Debug, Clone)]
+pub struct CheckStatus {
+    /// `success`, `failure`, `pending`, or `none` when nothing ran.
+    pub state: &'static str,
+    /// Names of the check runs and statuses that failed.
+    pub failing: Vec<String>,
+}
+
+/// Sums up the check runs of a commit: failed if any failed, pending if any
+/// hasn't finished, and successful otherwise.
+pub fn summarize(runs: &[CheckRun]) -> CheckStatus {
+    combine(&[], runs)
+}
+
+/// Sums up the commit statuses and check runs of a commit together, the
+/// way GitHub does for the checkmark next to a PR's head.
+pub fn combine(contexts: &[StatusContext], runs: &[CheckRun]) -> CheckStatus {
+    let failing_contexts = contexts
+        .iter()
+        .filter(|context| matches!(context.state.as_str(), "failure" | "error"))
+        .map(|context| context.context.clone());
+    let failing_runs = runs
+        .iter()
+        .filter(|run| {
+            run.conclusion
+                .as_deref()
+                .is_some_and(|conclusion| FAILED.contains(&conclusion))
+        })
+        .map(|run| run.name.clone());
+    let failing: Vec<String> = failing_contexts.chain(failing_runs).collect();
+    let pending = contexts.iter().any(|context| context.state == "pending")
+        || runs.iter().any(|run| run.status != "completed");
+    let state = if !failing.is_empty() {
+        "failure"
+    } else if pending {
+        "pending"
+    } else if contexts.is_empty() && runs.is_empty() {
+        "none"
+    } else {
+        "success"
+    };
+    CheckStatus { state, failing }
+}
+
+impl CheckStatus {
+    /// Whether nothing failed or is still running; a commit without any
+    /// checks passes.
+    pub fn is_green(&self) -> bool {
+        matches!(self.state, "success" | "none")
+    }
+
+    /// `✓`, `✗` followed by the failing checks, `pending`, or nothing.
+    pub fn describe(&self) -> String {
+        match self.state {
+            "success" => "✓".to_string(),
+            "failure" => format!("✗ {}", self.failing.join(", ")),
+            "pending" => "pending".to_string(),
+            _ => String::new(),
+        }
+    }
+}
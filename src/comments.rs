@@ -0,0 +1,172 @@
+use crate::output::Renderer;
+use crate::prs::ListFormat;
+use crate::table::{Cell, Table, TableStyle};
+use crate::PrReport;
+use serde::Serialize;
+use std::error::Error;
+use std::io::Write;
+
+/// A comment on a PR, as `comments` lists it.
+#[derive(Serialize)]
+struct CommentRow {
+    repository: String,
+    pr_number: u32,
+    /// `review` for a comment on a line of the diff, `conversation` for one
+    /// on the PR as a whole.
+    kind: &'static str,
+    path: Option<String>,
+    line: Option<u32>,
+    author: String,
+    created_at: String,
+    body: String,
+    url: String,
+}
+
+/// The comments of a PR, in the order they were made.
+fn rows(report: &PrReport) -> Vec<CommentRow> {
+    let repository = report.repository.to_string();
+    let conversation = report
+        .issue_comments
+        .iter()
+        .flatten()
+        .map(|comment| CommentRow {
+            repository: repository.clone(),
+            pr_number: report.number,
+            kind: "conversation",
+            path: None,
+            line: None,
+            author: comment.author().to_string(),
+            created_at: comment.created_at.clone(),
+            body: comment.body.clone(),
+            url: comment.html_url.clone(),
+        });
+    let mut rows: Vec<CommentRow> = report
+        .review_comments
+        .iter()
+        .flatten()
+        .map(|comment| CommentRow {
+            repository: repository.clone(),
+            pr_number: report.number,
+            kind: "review",
+            path: Some(comment.path.clone()),
+            line: comment.line.or(comment.original_line),
+            author: comment.author().to_string(),
+            created_at: comment.created_at.clone(),
+            body: comment.body.clone(),
+            url: comment.html_url.clone(),
+        })
+        .chain(conversation)
+        .collect();
+    // GitHub's timestamps are all in UTC, so they sort as text.
+    rows.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    rows
+}
+
+/// Lists the comments of each PR, one per row. The table only shows the
+/// first line of each comment.
+pub fn renderer(
+    format: ListFormat,
+    show_repository: bool,
+    quiet: bool,
+    out: Box<dyn Write>,
+) -> Box<dyn Renderer> {
+    match format {
+        ListFormat::Table => Box::new(TableRenderer {
+            out,
+            show_repository,
+            quiet,
+            table: Table::new(
+                ["PR", "File", "Line", "Author", "Date", "Comment"]
+                    .map(str::to_string)
+                    .to_vec(),
+            ),
+        }),
+        ListFormat::Json => Box::new(JsonRenderer {
+            out,
+            comments: Vec::new(),
+        }),
+        ListFormat::Csv => Box::new(CsvRenderer {
+            out: csv::WriterBuilder::new()
+                .has_headers(!quiet)
+                .from_writer(out),
+        }),
+    }
+}
+
+struct TableRenderer {
+    out: Box<dyn Write>,
+    show_repository: bool,
+    quiet: bool,
+    table: Table,
+}
+
+impl Renderer for TableRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        for row in rows(report) {
+            let label = if self.show_repository {
+                format!("{}#{}", row.repository, row.pr_number)
+            } else {
+                format!("#{}", row.pr_number)
+            };
+            let line = row.line.map(|line| line.to_string()).unwrap_or_default();
+            self.table.add_row(vec![
+                Cell::from(label.as_str()),
+                Cell::from(row.path.as_deref().unwrap_or_default()),
+                Cell::from(line.as_str()),
+                Cell::from(row.author.as_str()),
+                Cell::from(row.created_at.as_str()),
+                Cell::from(row.body.lines().next().unwrap_or_default()),
+            ]);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let style = if self.quiet {
+            self.table.hide_headers();
+            TableStyle::Borderless
+        } else {
+            TableStyle::Ascii
+        };
+        self.table.render(&mut self.out, style, None)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+struct JsonRenderer {
+    out: Box<dyn Write>,
+    comments: Vec<CommentRow>,
+}
+
+impl Renderer for JsonRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        self.comments.extend(rows(report));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&mut self.out, &self.comments)?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+}
+
+/// The header row comes from the field names of [`CommentRow`].
+struct CsvRenderer {
+    out: csv::Writer<Box<dyn Write>>,
+}
+
+impl Renderer for CsvRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        for row in rows(report) {
+            self.out.serialize(row)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+use crate::github::Commit;
+use crate::output::{self, Renderer};
+use crate::prs::ListFormat;
+use crate::table::{Cell, Table, TableStyle};
+use crate::PrReport;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::io::Write;
+
+/// A hash of the change a patch makes, the same for a commit and its
+/// cherry-picks: like `git patch-id`, it leaves out the commit message,
+/// the blob IDs, the line numbers and whitespace.
+pub fn patch_id(patch: &str) -> Option<String> {
+    let start = patch.find("diff --git ")?;
+    let diff = &patch[start..];
+    // `git format-patch` signs off with `-- ` and the git version.
+    let diff = diff.rfind("\n-- \n").map_or(diff, |end| &diff[..end]);
+    let mut hasher = Sha256::new();
+    for line in diff.lines() {
+        if line.starts_with("index ") || line.starts_with("@@") {
+            continue;
+        }
+        let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    Some(
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+    )
+}
+
+/// A commit of one of the PRs, with what it is matched by.
+struct Entry {
+    sha: String,
+    subject: String,
+    patch_id: Option<String>,
+}
+
+impl Entry {
+    fn new(commit: &Commit) -> Entry {
+        Entry {
+            sha: commit.sha.clone(),
+            subject: commit
+                .commit
+                .message
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            patch_id: commit.patch.as_deref().and_then(patch_id),
+        }
+    }
+}
+
+/// The commits of a PR to compare, oldest first, without merge commits,
+/// which only bring the base branch in.
+fn entries(report: &PrReport) -> Vec<Entry> {
+    report
+        .commits
+        .iter()
+        .filter(|commit| !commit.is_merge())
+        .map(Entry::new)
+        .collect()
+}
+
+/// A commit of either PR, and the commit of the other PR it matches.
+#[derive(Serialize)]
+struct Row {
+    left_sha: Option<String>,
+    right_sha: Option<String>,
+    /// `sha` when both PRs have the commit, `patch-id` when they have
+    /// commits making the same change, and none when only one has it.
+    matched_by: Option<&'static str>,
+    message: String,
+}
+
+/// Pairs up the commits of the left PR with those of the right one, by
+/// SHA and then by patch ID. The left PR's commits come first in their
+/// order, then those only the right PR has.
+fn rows(left: &[Entry], right: &[Entry]) -> Vec<Row> {
+    let mut used = vec![false; right.len()];
+    let mut rows = Vec::new();
+    for entry in left {
+        let by_sha = (0..right.len()).find(|&i| !used[i] && right[i].sha == entry.sha);
+        let found = match by_sha {
+            Some(i) => Some((i, "sha")),
+            None => (0..right.len())
+                .find(|&i| {
+                    !used[i] && entry.patch_id.is_some() && right[i].patch_id == entry.patch_id
+                })
+                .map(|i| (i, "patch-id")),
+        };
+        if let Some((i, _)) = found {
+            used[i] = true;
+        }
+        rows.push(Row {
+            left_sha: Some(entry.sha.clone()),
+            right_sha: found.map(|(i, _)| right[i].sha.clone()),
+            matched_by: found.map(|(_, by)| by),
+            message: entry.subject.clone(),
+        });
+    }
+    for (entry, _) in right.iter().zip(used).filter(|(_, used)| !used) {
+        rows.push(Row {
+            left_sha: None,
+            right_sha: Some(entry.sha.clone()),
+            matched_by: None,
+            message: entry.subject.clone(),
+        });
+    }
+    rows
+}
+
+/// The comparison `json` writes.
+#[derive(Serialize)]
+struct Comparison {
+    left: String,
+    right: String,
+    commits: Vec<Row>,
+}
+
+/// Collects the two PRs, then lists their commits side by side.
+pub fn renderer(
+    format: ListFormat,
+    show_repository: bool,
+    quiet: bool,
+    out: Box<dyn Write>,
+) -> Box<dyn Renderer> {
+    Box::new(CompareRenderer {
+        out,
+        format,
+        show_repository,
+        quiet,
+        reports: Vec::new(),
+    })
+}
+
+struct CompareRenderer {
+    out: Box<dyn Write>,
+    format: ListFormat,
+    show_repository: bool,
+    quiet: bool,
+    reports: Vec<(String, Vec<Entry>)>,
+}
+
+impl CompareRenderer {
+    fn write_table(&mut self, left: &str, right: &str, rows: &[Row]) -> Result<(), Box<dyn Error>> {
+        let mut table = Table::new(vec![
+            left.to_string(),
+            right.to_string(),
+            "Match".to_string(),
+            "Message".to_string(),
+        ]);
+        let short = |sha: &Option<String>| {
+            sha.as_deref()
+                .map(|sha| output::short_sha(sha).to_string())
+                .unwrap_or_default()
+        };
+        for row in rows {
+            table.add_row(vec![
+                Cell::from(short(&row.left_sha).as_str()),
+                Cell::from(short(&row.right_sha).as_str()),
+                Cell::from(row.matched_by.unwrap_or_default()),
+                Cell::from(row.message.as_str()),
+            ]);
+        }
+        let style = if self.quiet {
+            table.hide_headers();
+            TableStyle::Borderless
+        } else {
+            TableStyle::Ascii
+        };
+        table.render(&mut self.out, style, None)?;
+        if !self.quiet {
+            let only = |side: fn(&Row) -> &Option<String>| {
+                rows.iter()
+                    .filter(|row| row.matched_by.is_none() && side(row).is_some())
+                    .count()
+            };
+            writeln!(
+                self.out,
+                "\n{} matched, {} only in {}, {} only in {}",
+                rows.iter().filter(|row| row.matched_by.is_some()).count(),
+                only(|row| &row.left_sha),
+                left,
+                only(|row| &row.right_sha),
+                right
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for CompareRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        let label = if self.show_repository {
+            format!("{}#{}", report.repository, report.number)
+        } else {
+            format!("#{}", report.number)
+        };
+        self.reports.push((label, entries(report)));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let reports = std::mem::take(&mut self.reports);
+        let [(left, left_entries), (right, right_entries)] = reports.as_slice() else {
+            return Err(format!("compare takes exactly two PRs, but got {}", reports.len()).into());
+        };
+        let rows = rows(left_entries, right_entries);
+        match self.format {
+            ListFormat::Table => self.write_table(left, right, &rows)?,
+            ListFormat::Json => {
+                let comparison = Comparison {
+                    left: left.clone(),
+                    right: right.clone(),
+                    commits: rows,
+                };
+                serde_json::to_writer_pretty(&mut self.out, &comparison)?;
+                writeln!(self.out)?;
+            }
+            ListFormat::Csv => {
+                let mut out = csv::WriterBuilder::new()
+                    .has_headers(!self.quiet)
+                    .from_writer(&mut self.out);
+                for row in rows {
+                    out.serialize(row)?;
+                }
+                out.flush()?;
+            }
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "From 1111 Mon Sep 17 00:00:00 2001
+From: Ada <ada@x>
+Subject: [PATCH] Fix the thing
+
+---
+ src/main.rs | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    old();
++    new();
+ }
+-- 
+2.43.0
+";
+
+    fn entry(sha: &str, patch_id: Option<&str>) -> Entry {
+        Entry {
+            sha: sha.to_string(),
+            subject: format!("Commit {}", sha),
+            patch_id: patch_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn patch_id_needs_a_diff() {
+        assert_eq!(patch_id("Subject: nothing to see"), None);
+        assert!(patch_id(PATCH).is_some());
+    }
+
+    #[test]
+    fn patch_id_ignores_what_a_cherry_pick_changes() {
+        let picked = PATCH
+            .replace("From 1111", "From 3333")
+            .replace("Fix the thing", "Fix the thing (cherry picked)")
+            .replace("index 1111111..2222222", "index 4444444..5555555")
+            .replace("@@ -1,3 +1,3 @@", "@@ -10,3 +10,3 @@")
+            .replace("    new();", "\tnew();  ")
+            .replace("2.43.0", "2.39.1");
+        assert_eq!(patch_id(&picked), patch_id(PATCH));
+    }
+
+    #[test]
+    fn patch_id_tells_changes_apart() {
+        let other = PATCH.replace("new();", "newer();");
+        assert_ne!(patch_id(&other), patch_id(PATCH));
+    }
+
+    #[test]
+    fn rows_match_by_sha_then_patch_id() {
+        let left = [
+            entry("a", Some("1")),
+            entry("b", Some("2")),
+            entry("c", None),
+            entry("d", Some("4")),
+        ];
+        let right = [
+            entry("x", Some("2")),
+            entry("a", Some("1")),
+            entry("y", None),
+        ];
+        let rows = rows(&left, &right);
+        let summary: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.left_sha.as_deref(),
+                    row.right_sha.as_deref(),
+                    row.matched_by,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Some("a"), Some("a"), Some("sha")),
+                (Some("b"), Some("x"), Some("patch-id")),
+                (Some("c"), None, None),
+                (Some("d"), None, None),
+                (None, Some("y"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_match_each_commit_once() {
+        let left = [entry("a", Some("1")), entry("b", Some("1"))];
+        let right = [entry("x", Some("1"))];
+        let matched: Vec<_> = rows(&left, &right)
+            .iter()
+            .map(|row| row.right_sha.clone())
+            .collect();
+        assert_eq!(matched, [Some("x".to_string()), None]);
+    }
+
+    #[test]
+    fn rows_leave_commits_without_a_patch_unmatched() {
+        let rows = rows(&[entry("a", None)], &[entry("b", None)]);
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.matched_by.is_none()));
+    }
+}
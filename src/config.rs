@@ -0,0 +1,87 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Settings read from the config file, used for anything not given on the
+/// command line.
+#[derive(Deserialize, Default, Debug, Clone)]
+pub struct Profile {
+    pub owner: Option<String>,
+    pub repo: Option<String>,
+    pub token_path: Option<PathBuf>,
+    pub api_url: Option<String>,
+    pub format: Option<String>,
+}
+
+impl Profile {
+    /// `self`, with whatever `other` sets taking precedence.
+    fn overridden_by(self, other: Profile) -> Profile {
+        Profile {
+            owner: other.owner.or(self.owner),
+            repo: other.repo.or(self.repo),
+            token_path: other.token_path.or(self.token_path),
+            api_url: other.api_url.or(self.api_url),
+            format: other.format.or(self.format),
+        }
+    }
+}
+
+/// The config file: settings at the top level apply to every run, and each
+/// `[profiles.NAME]` table overrides them when picked with `--profile NAME`.
+#[derive(Deserialize, Default, Debug)]
+struct File {
+    #[serde(flatten)]
+    defaults: Profile,
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+/// Where the config file lives: `~/.config/pr-commits/config.toml` on Linux,
+/// or the platform's equivalent.
+pub fn path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("pr-commits").join("config.toml"))
+}
+
+/// Loads the settings of `profile`, or just the top-level ones without a
+/// profile. A missing config file is only an error when a profile is asked
+/// for.
+pub fn load(profile: Option<&str>) -> Result<Profile, Box<dyn Error>> {
+    let Some(path) = path() else {
+        return without_file(profile);
+    };
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return without_file(profile),
+        Err(err) => return Err(format!("can't read {}: {}", path.display(), err).into()),
+    };
+    let mut file: File = toml::from_str(&contents)
+        .map_err(|err| format!("invalid config file {}: {}", path.display(), err))?;
+    let mut settings = match profile {
+        Some(name) => {
+            let overrides = file
+                .profiles
+                .remove(name)
+                .ok_or_else(|| format!("no profile {:?} in {}", name, path.display()))?;
+            file.defaults.overridden_by(overrides)
+        }
+        None => file.defaults,
+    };
+    settings.token_path = settings.token_path.map(expand_home);
+    Ok(settings)
+}
+
+fn without_file(profile: Option<&str>) -> Result<Profile, Box<dyn Error>> {
+    match profile {
+        Some(name) => Err(format!("no config file to read profile {:?} from", name).into()),
+        None => Ok(Profile::default()),
+    }
+}
+
+/// Expands a leading `~/` to the home directory.
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, Utc};
+use std::borrow::Cow;
+
+/// Timezone that commit dates are converted to before formatting.
+#[derive(Clone, Copy, Debug)]
+pub enum Timezone {
+    Local,
+    Utc,
+    Named(chrono_tz::Tz),
+}
+
+/// Parses `local`, `utc` or an IANA name such as `Europe/Berlin`.
+pub fn parse_timezone(value: &str) -> Result<Timezone, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "local" => Ok(Timezone::Local),
+        "utc" => Ok(Timezone::Utc),
+        _ => value
+            .parse()
+            .map(Timezone::Named)
+            .map_err(|_| format!("unknown timezone '{}'", value)),
+    }
+}
+
+/// Checks that `value` is a valid strftime format string up front, since
+/// chrono only reports bad specifiers when a date is actually formatted.
+pub fn parse_date_format(value: &str) -> Result<String, String> {
+    if StrftimeItems::new(value).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("invalid date format '{}'", value));
+    }
+    Ok(value.to_string())
+}
+
+/// Parses a calendar date written as `YYYY-MM-DD`.
+pub fn parse_day(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD", value))
+}
+
+const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+
+/// How commit dates are rendered. With neither a format nor a timezone the
+/// API's ISO 8601 timestamps are shown unchanged.
+#[derive(Clone, Debug, Default)]
+pub struct DateDisplay {
+    pub format: Option<String>,
+    pub timezone: Option<Timezone>,
+    /// Show "3 days ago" instead of a timestamp.
+    pub relative: bool,
+}
+
+impl DateDisplay {
+    pub fn render<'a>(&self, raw: &'a str) -> Cow<'a, str> {
+        if self.format.is_none() && self.timezone.is_none() && !self.relative {
+            return Cow::Borrowed(raw);
+        }
+        let Ok(date) = DateTime::parse_from_rfc3339(raw) else {
+            return Cow::Borrowed(raw);
+        };
+        if self.relative {
+            return Cow::Owned(relative(Utc::now().signed_duration_since(date)));
+        }
+        let format = self.format.as_deref().unwrap_or(DEFAULT_FORMAT);
+        Cow::Owned(match self.timezone {
+            None => date.format(format).to_string(),
+            Some(Timezone::Local) => date.with_timezone(&Local).format(format).to_string(),
+            Some(Timezone::Utc) => date.with_timezone(&Utc).format(format).to_string(),
+            Some(Timezone::Named(tz)) => date.with_timezone(&tz).format(format).to_string(),
+        })
+    }
+}
+
+/// Renders how long ago something happened, in the largest whole unit.
+fn relative(elapsed: TimeDelta) -> String {
+    const UNITS: [(i64, &str); 6] = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+    let seconds = elapsed.num_seconds();
+    let Some(&(size, unit)) = UNITS.iter().find(|(size, _)| seconds.abs() >= *size) else {
+        return "just now".to_string();
+    };
+    let count = seconds.abs() / size;
+    let plural = if count == 1 { "" } else { "s" };
+    if seconds < 0 {
+        format!("in {} {}{}", count, unit, plural)
+    } else {
+        format!("{} {}{} ago", count, unit, plural)
+    }
+}
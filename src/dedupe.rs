@@ -0,0 +1,65 @@
+use crate::compare::patch_id;
+use crate::github::Commit;
+use crate::PrReport;
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+/// What makes commits of different PRs the same, for `--dedupe`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dedupe {
+    /// The same commit
+    Sha,
+    /// The same commit, or commits making the same change, as after a
+    /// rebase or a cherry-pick
+    PatchId,
+}
+
+/// What `commit` is known by: its SHA and, by patch ID, the change it
+/// makes.
+fn keys(commit: &Commit, by: Dedupe) -> Vec<String> {
+    let mut keys = vec![commit.sha.clone()];
+    if by == Dedupe::PatchId {
+        if let Some(id) = commit.patch.as_deref().and_then(patch_id) {
+            keys.push(format!("patch-id {}", id));
+        }
+    }
+    keys
+}
+
+/// Leaves each commit only to the first of `reports` that has it, and
+/// lists on it the other PRs that have it too. Co-authors expanded into
+/// rows of the same commit stay together.
+pub fn collapse(reports: &mut [PrReport], by: Dedupe, show_repository: bool) {
+    // The report each commit was kept in, and its index there.
+    let mut kept: HashMap<String, (usize, usize)> = HashMap::new();
+    for at in 0..reports.len() {
+        let report = &reports[at];
+        let label = if show_repository {
+            format!("{}#{}", report.repository, report.number)
+        } else {
+            format!("#{}", report.number)
+        };
+        let commits = std::mem::take(&mut reports[at].commits);
+        let mut own = Vec::new();
+        for commit in commits {
+            let keys = keys(&commit, by);
+            match keys.iter().find_map(|key| kept.get(key)) {
+                Some(&(first, index)) if first != at => {
+                    let also_in = reports[first].commits[index]
+                        .also_in
+                        .get_or_insert_with(Vec::new);
+                    if !also_in.contains(&label) {
+                        also_in.push(label.clone());
+                    }
+                }
+                _ => {
+                    for key in keys {
+                        kept.entry(key).or_insert((at, own.len()));
+                    }
+                    own.push(commit);
+                }
+            }
+        }
+        reports[at].commits = own;
+    }
+}
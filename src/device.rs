@@ -0,0 +1,79 @@
+use serde::Deserialize;
+use std::error::Error;
+use std::time::Duration;
+
+/// Extra wait GitHub asks for with every `slow_down` response.
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    /// Seconds to wait between polls.
+    interval: u64,
+}
+
+#[derive(Deserialize)]
+struct Poll {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Gets a token through the OAuth device flow: the user enters a code in
+/// their browser while this polls until they have authorized it.
+pub async fn login(host: &str, client_id: &str, scopes: &str) -> Result<String, Box<dyn Error>> {
+    let client = reqwest::Client::new();
+    let code: DeviceCode = client
+        .post(format!("https://{}/login/device/code", host))
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&[("client_id", client_id), ("scope", scopes)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    eprintln!(
+        "Open {} and enter the code {}",
+        code.verification_uri, code.user_code
+    );
+
+    let mut interval = Duration::from_secs(code.interval);
+    loop {
+        tokio::time::sleep(interval).await;
+        let poll: Poll = client
+            .post(format!("https://{}/login/oauth/access_token", host))
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&[
+                ("client_id", client_id),
+                ("device_code", code.device_code.as_str()),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(token) = poll.access_token {
+            return Ok(token);
+        }
+        match poll.error.as_deref() {
+            Some("authorization_pending") => {}
+            Some("slow_down") => interval += SLOW_DOWN_INCREMENT,
+            Some("expired_token") => return Err("the code expired before it was entered".into()),
+            Some("access_denied") => return Err("authorization was denied".into()),
+            error => {
+                return Err(format!(
+                    "device flow failed: {}",
+                    poll.error_description
+                        .as_deref()
+                        .or(error)
+                        .unwrap_or("no token in the response")
+                )
+                .into())
+            }
+        }
+    }
+}
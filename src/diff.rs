@@ -0,0 +1,68 @@
+use crate::color;
+use crate::output::Renderer;
+use crate::PrReport;
+use std::error::Error;
+use std::io::Write;
+
+/// Writes the diff of each PR, or of the commit asked for, one after the
+/// other so that the output still applies with `git apply`.
+pub fn renderer(color: bool, out: Box<dyn Write>) -> Box<dyn Renderer> {
+    Box::new(DiffRenderer { out, color })
+}
+
+struct DiffRenderer {
+    out: Box<dyn Write>,
+    color: bool,
+}
+
+impl Renderer for DiffRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        let diff = report.diff.as_deref().unwrap_or_default();
+        // Line endings are written as they come, for a CRLF file or one
+        // without a newline at the end to still apply.
+        for piece in diff.split_inclusive('\n') {
+            let line = piece.trim_end_matches(['\n', '\r']);
+            match style(line).filter(|_| self.color) {
+                Some(style) => write!(
+                    self.out,
+                    "{}{}",
+                    color::paint(style, line),
+                    &piece[line.len()..]
+                )?,
+                None => self.out.write_all(piece.as_bytes())?,
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// How `git diff --color` would show a line of a diff.
+fn style(line: &str) -> Option<&'static str> {
+    const HEADERS: [&str; 9] = [
+        "diff --git ",
+        "index ",
+        "--- ",
+        "+++ ",
+        "new file mode",
+        "deleted file mode",
+        "similarity index",
+        "rename from",
+        "rename to",
+    ];
+    if HEADERS.iter().any(|header| line.starts_with(header)) {
+        Some(color::BOLD)
+    } else if line.starts_with("@@") {
+        Some(color::CYAN)
+    } else if line.starts_with('+') {
+        Some(color::GREEN)
+    } else if line.starts_with('-') {
+        Some(color::RED)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,184 @@
+use crate::ratelimit;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+/// Failures talking to GitHub that scripts may want to tell apart, each with
+/// its own exit code. Anything else is reported with [`GENERAL_EXIT_CODE`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{url} not found")]
+    NotFound { url: String },
+    #[error("GitHub refused access to {url}: {message}")]
+    Unauthorized {
+        url: String,
+        status: StatusCode,
+        message: String,
+    },
+    #[error("{}", describe_rate_limit(*reset, message))]
+    RateLimited {
+        reset: Option<DateTime<Utc>>,
+        message: String,
+    },
+    #[error(transparent)]
+    Network(#[from] reqwest::Error),
+    #[error("couldn't decode the response from {url}: {source}")]
+    Decode {
+        url: String,
+        source: serde_json::Error,
+    },
+    #[error("{failed} of {total} PRs failed")]
+    PartialFailure { failed: usize, total: usize },
+    #[error("no commits in PR {prs}")]
+    Empty { prs: String },
+    #[error("CI isn't passing on the head of PR {prs}")]
+    NotGreen { prs: String },
+    #[error("PR {prs} can't be merged for conflicts")]
+    Conflicts { prs: String },
+    #[error("{status} from {url}: {message}")]
+    Api {
+        status: StatusCode,
+        url: String,
+        message: String,
+    },
+    /// Any of the above while fetching some PRs, explained in terms of them.
+    #[error("{}", describe_pr(prs, repo, source))]
+    Pr {
+        /// The PRs, as in `#1, #2`.
+        prs: String,
+        /// `owner/repo`.
+        repo: String,
+        source: Box<Error>,
+    },
+}
+
+/// Exit code for errors that aren't an [`Error`].
+pub const GENERAL_EXIT_CODE: u8 = 1;
+
+/// Shown at the end of `--help`.
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  Success
+  1  Any other error
+  2  Invalid command line
+  3  A repository or PR was not found
+  4  The token was rejected or lacks access
+  5  The API rate limit was exceeded
+  6  Network error or timeout
+  7  GitHub sent a response that couldn't be decoded
+  8  Some PRs failed to fetch with --keep-going
+  9  Some PRs have no commits with --fail-on-empty
+ 10  CI is failing or pending on some PR heads with --require-green
+ 11  Some PRs have merge conflicts with --fail-on-conflicts";
+
+/// The JSON body GitHub sends with error responses.
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+impl Error {
+    /// Classifies an unsuccessful response by its status and the message in
+    /// its body.
+    pub fn from_response(url: &str, status: StatusCode, body: &str) -> Error {
+        let message = serde_json::from_str::<ErrorBody>(body)
+            .map(|body| body.message)
+            .unwrap_or_else(|_| body.trim().to_string());
+        let url = url.to_string();
+        match status {
+            StatusCode::NOT_FOUND => Error::NotFound { url },
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+                if message.to_lowercase().contains("rate limit") =>
+            {
+                Error::RateLimited {
+                    reset: None,
+                    message,
+                }
+            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::Unauthorized {
+                url,
+                status,
+                message,
+            },
+            status => Error::Api {
+                status,
+                url,
+                message,
+            },
+        }
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::NotFound { .. } => 3,
+            Error::Unauthorized { .. } => 4,
+            Error::RateLimited { .. } => 5,
+            Error::Network(_) => 6,
+            Error::Decode { .. } => 7,
+            Error::PartialFailure { .. } => 8,
+            Error::Empty { .. } => 9,
+            Error::NotGreen { .. } => 10,
+            Error::Conflicts { .. } => 11,
+            Error::Api { .. } => GENERAL_EXIT_CODE,
+            Error::Pr { source, .. } => source.exit_code(),
+        }
+    }
+
+    /// Whether what was asked for doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Error::NotFound { .. } => true,
+            Error::Pr { source, .. } => source.is_not_found(),
+            _ => false,
+        }
+    }
+
+    /// The URL of the request that failed, if there was one.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Error::NotFound { url }
+            | Error::Unauthorized { url, .. }
+            | Error::Decode { url, .. }
+            | Error::Api { url, .. } => Some(url),
+            Error::Network(err) => err.url().map(|url| url.as_str()),
+            Error::Pr { source, .. } => source.url(),
+            Error::RateLimited { .. }
+            | Error::PartialFailure { .. }
+            | Error::Empty { .. }
+            | Error::NotGreen { .. }
+            | Error::Conflicts { .. } => None,
+        }
+    }
+}
+
+fn describe_rate_limit(reset: Option<DateTime<Utc>>, message: &str) -> String {
+    match reset {
+        Some(reset) => format!(
+            "GitHub API rate limit exceeded; rate limited until {}",
+            ratelimit::describe_reset(reset)
+        ),
+        None => format!("rate limited: {}", message),
+    }
+}
+
+fn describe_pr(prs: &str, repo: &str, source: &Error) -> String {
+    match source {
+        // GitHub hides private repositories the token can't see behind a
+        // 404 too.
+        Error::NotFound { .. } => format!(
+            "PR {} not found in {} (or the token can't see the repository)",
+            prs, repo
+        ),
+        Error::Unauthorized {
+            status: StatusCode::UNAUTHORIZED,
+            message,
+            ..
+        } => format!("the GitHub token was rejected: {}", message),
+        Error::Unauthorized { message, .. } => {
+            format!("the token lacks access to {}: {}", repo, message)
+        }
+        Error::Network(err) if err.is_timeout() => format!("timed out fetching PR {}", prs),
+        Error::RateLimited { .. } => source.to_string(),
+        source => format!("PR {}: {}", prs, source),
+    }
+}
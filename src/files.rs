@@ -0,0 +1,150 @@
+use crate::output::Renderer;
+use crate::prs::ListFormat;
+use crate::table::{Cell, Table, TableStyle};
+use crate::PrReport;
+use serde::Serialize;
+use std::error::Error;
+use std::io::Write;
+
+/// A file a PR changes, as `files` lists it.
+#[derive(Serialize)]
+struct FileRow {
+    repository: String,
+    pr_number: u32,
+    filename: String,
+    /// `added`, `modified`, `removed`, `renamed`, `copied` or `changed`.
+    status: String,
+    additions: u32,
+    deletions: u32,
+    /// The name the file had before, when it was renamed.
+    previous_filename: Option<String>,
+}
+
+/// The files of a PR, in the order GitHub lists them.
+fn rows(report: &PrReport) -> Vec<FileRow> {
+    let repository = report.repository.to_string();
+    report
+        .files
+        .iter()
+        .flatten()
+        .map(|file| FileRow {
+            repository: repository.clone(),
+            pr_number: report.number,
+            filename: file.filename.clone(),
+            status: file.status.clone(),
+            additions: file.additions,
+            deletions: file.deletions,
+            previous_filename: file.previous_filename.clone(),
+        })
+        .collect()
+}
+
+/// Lists the files each PR changes, one per row.
+pub fn renderer(
+    format: ListFormat,
+    show_repository: bool,
+    quiet: bool,
+    out: Box<dyn Write>,
+) -> Box<dyn Renderer> {
+    match format {
+        ListFormat::Table => Box::new(TableRenderer {
+            out,
+            show_repository,
+            quiet,
+            table: Table::new(
+                ["PR", "File", "Status", "+", "-"]
+                    .map(str::to_string)
+                    .to_vec(),
+            ),
+        }),
+        ListFormat::Json => Box::new(JsonRenderer {
+            out,
+            files: Vec::new(),
+        }),
+        ListFormat::Csv => Box::new(CsvRenderer {
+            out: csv::WriterBuilder::new()
+                .has_headers(!quiet)
+                .from_writer(out),
+        }),
+    }
+}
+
+struct TableRenderer {
+    out: Box<dyn Write>,
+    show_repository: bool,
+    quiet: bool,
+    table: Table,
+}
+
+impl Renderer for TableRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        for row in rows(report) {
+            let label = if self.show_repository {
+                format!("{}#{}", row.repository, row.pr_number)
+            } else {
+                format!("#{}", row.pr_number)
+            };
+            let filename = match &row.previous_filename {
+                Some(previous) => format!("{} → {}", previous, row.filename),
+                None => row.filename.clone(),
+            };
+            self.table.add_row(vec![
+                Cell::from(label.as_str()),
+                Cell::from(filename.as_str()),
+                Cell::from(row.status.as_str()),
+                Cell::from(row.additions.to_string().as_str()),
+                Cell::from(row.deletions.to_string().as_str()),
+            ]);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let style = if self.quiet {
+            self.table.hide_headers();
+            TableStyle::Borderless
+        } else {
+            TableStyle::Ascii
+        };
+        self.table.render(&mut self.out, style, None)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+struct JsonRenderer {
+    out: Box<dyn Write>,
+    files: Vec<FileRow>,
+}
+
+impl Renderer for JsonRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        self.files.extend(rows(report));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&mut self.out, &self.files)?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+}
+
+/// The header row comes from the field names of [`FileRow`].
+struct CsvRenderer {
+    out: csv::Writer<Box<dyn Write>>,
+}
+
+impl Renderer for CsvRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        for row in rows(report) {
+            self.out.serialize(row)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
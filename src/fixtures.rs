@@ -0,0 +1,109 @@
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// A response captured with `--record`, replayed with `--replay`.
+#[derive(Serialize, Deserialize, Debug)]
+struct Fixture {
+    method: String,
+    url: String,
+    /// Body of the request, for POSTs such as GraphQL queries.
+    request: Option<String>,
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+/// A directory of recorded responses, one JSON file per request. Requests
+/// are told apart by method, URL and body, never by their credentials,
+/// so a recording can be replayed without a token.
+pub struct Fixtures {
+    dir: PathBuf,
+}
+
+impl Fixtures {
+    pub fn new(dir: PathBuf) -> Self {
+        Fixtures { dir }
+    }
+
+    fn path(&self, method: &str, url: &str, request: Option<&str>) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{} {}\n", method, url));
+        hasher.update(request.unwrap_or_default());
+        let name: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// The recorded response to `request`.
+    pub fn replay(&self, request: &Request) -> Result<Response, Box<dyn Error>> {
+        let body = request_body(request);
+        let path = self.path(request.method().as_str(), request.url().as_str(), body);
+        let contents = std::fs::read_to_string(&path).map_err(|_| {
+            format!(
+                "no recorded response for {} {} in {}",
+                request.method(),
+                request.url(),
+                self.dir.display()
+            )
+        })?;
+        let fixture: Fixture = serde_json::from_str(&contents)
+            .map_err(|err| format!("invalid recording {}: {}", path.display(), err))?;
+        rebuild(fixture.status, &fixture.headers, fixture.body)
+    }
+
+    /// Writes `response` to disk as the answer to `request`, and hands back
+    /// a copy of it since reading the body used up the original.
+    pub async fn record(
+        &self,
+        request: &Request,
+        response: Response,
+    ) -> Result<Response, Box<dyn Error>> {
+        let status = response.status().as_u16();
+        let headers: BTreeMap<String, String> = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = response
+            .text()
+            .await
+            .map_err(crate::error::Error::Network)?;
+        let fixture = Fixture {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            request: request_body(request).map(str::to_string),
+            status,
+            headers,
+            body,
+        };
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|err| format!("can't create {}: {}", self.dir.display(), err))?;
+        let path = self.path(&fixture.method, &fixture.url, fixture.request.as_deref());
+        std::fs::write(&path, serde_json::to_string_pretty(&fixture)?)
+            .map_err(|err| format!("can't write {}: {}", path.display(), err))?;
+        rebuild(fixture.status, &fixture.headers, fixture.body)
+    }
+}
+
+fn request_body(request: &Request) -> Option<&str> {
+    std::str::from_utf8(request.body()?.as_bytes()?).ok()
+}
+
+fn rebuild(
+    status: u16,
+    headers: &BTreeMap<String, String>,
+    body: String,
+) -> Result<Response, Box<dyn Error>> {
+    let mut builder = http::Response::builder().status(status);
+    for (name, value) in headers {
+        builder = builder.header(name, value);
+    }
+    Ok(Response::from(builder.body(body)?))
+}
@@ -0,0 +1,1352 @@
+use crate::app::AppAuth;
+use crate::cache::{self, Cache};
+use crate::ci::CheckStatus;
+use crate::error;
+use crate::fixtures::Fixtures;
+use crate::issues::LinkedIssue;
+use crate::landed::Landing;
+use crate::pool::TokenPool;
+use crate::ratelimit::{self, OnRateLimit};
+use crate::redact;
+use clap::ValueEnum;
+use reqwest::header::{
+    HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, ETAG, IF_NONE_MATCH, LINK, USER_AGENT,
+};
+use reqwest::{Certificate, NoProxy, Proxy, RequestBuilder, Response, StatusCode};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// REST API root of github.com; GitHub Enterprise Server serves it under
+/// `https://HOST/api/v3`.
+pub const API_URL: &str = "https://api.github.com";
+
+/// REST API version sent in `X-GitHub-Api-Version` unless `--api-version`
+/// picks another one.
+pub const API_VERSION: &str = "2022-11-28";
+
+/// Media type asking for a PR or commit as a unified diff.
+const DIFF_MEDIA_TYPE: &str = "application/vnd.github.diff";
+
+/// Media type asking for a commit as a patch in mbox format, the way
+/// `git format-patch` writes them.
+const PATCH_MEDIA_TYPE: &str = "application/vnd.github.patch";
+
+/// The pulls commits endpoint stops at this many commits, however many
+/// pages are requested.
+const PULL_COMMITS_LIMIT: u32 = 250;
+
+/// The search API returns at most this many results, however many pages
+/// are requested.
+const SEARCH_LIMIT: u32 = 1000;
+
+/// How many times a single request is retried after hitting a secondary
+/// rate limit before giving up.
+const SECONDARY_LIMIT_RETRIES: u32 = 5;
+
+/// Which GitHub API PRs are fetched through.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Api {
+    /// Two or more REST calls per PR
+    Rest,
+    /// One GraphQL query per batch of PRs
+    Graphql,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Commit {
+    pub sha: String,
+    pub html_url: String,
+    pub commit: CommitInfo,
+    /// Only counted, as there is more than one for a merge commit.
+    #[serde(default)]
+    pub parents: Vec<IgnoredAny>,
+    /// Filled in with `--checks`.
+    #[serde(skip)]
+    pub checks: Option<CheckStatus>,
+    /// Filled in with `--show-files`.
+    #[serde(skip)]
+    pub files: Option<Vec<ChangedFile>>,
+    /// Filled in with `--diffstat`.
+    #[serde(skip)]
+    pub stats: Option<Diffstat>,
+    /// Filled in with `--download-patches`.
+    #[serde(skip)]
+    pub patch: Option<String>,
+    /// Filled in with `--verify-landed`.
+    #[serde(skip)]
+    pub landed: Option<Landing>,
+    /// With `--dedupe`, the other PRs that have the commit.
+    #[serde(skip)]
+    pub also_in: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PullRequest {
+    pub number: u32,
+    pub title: String,
+    pub html_url: String,
+    /// Who opened the PR; missing for deleted accounts.
+    pub user: Option<User>,
+    /// `open` or `closed`, merged PRs included.
+    pub state: String,
+    pub created_at: String,
+    pub merged_at: Option<String>,
+    #[serde(default)]
+    pub merged: bool,
+    /// Whether the PR merges without conflicts; `None` while GitHub is
+    /// still working it out, which it only starts on being asked.
+    pub mergeable: Option<bool>,
+    /// Such as `clean`, `dirty` (conflicts), `blocked`, `behind` or
+    /// `unstable`.
+    pub mergeable_state: Option<String>,
+    /// Once merged, the commit the PR landed as on its base branch: the
+    /// merge commit, the squashed commit or the last rebased one.
+    #[serde(default)]
+    pub merge_commit_sha: Option<String>,
+    /// The description, in Markdown; `None` when it is empty.
+    pub body: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// Missing on GitHub Enterprise Server versions without draft PRs.
+    #[serde(default)]
+    pub draft: bool,
+    /// The issues the PR closes, when they came with it from GraphQL.
+    #[serde(skip)]
+    pub closing_issues: Option<Vec<LinkedIssue>>,
+    /// Total number of commits, even past what the commits endpoint returns.
+    pub commits: u32,
+    pub base: GitRef,
+    pub head: GitRef,
+}
+
+impl PullRequest {
+    /// `open`, `merged` or `closed`.
+    pub fn status(&self) -> &'static str {
+        match (self.merged_at.is_some(), self.state.as_str()) {
+            (true, _) => "merged",
+            (false, "open") => "open",
+            (false, _) => "closed",
+        }
+    }
+
+    /// Whether the PR is open and can't be merged for conflicts.
+    pub fn has_conflicts(&self) -> bool {
+        self.state == "open" && self.mergeable == Some(false)
+    }
+
+    /// Login of whoever opened the PR.
+    pub fn author(&self) -> &str {
+        login(self.user.as_ref())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GitRef {
+    /// Branch name.
+    #[serde(rename = "ref")]
+    pub name: String,
+    pub sha: String,
+}
+
+impl Commit {
+    pub fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CommitInfo {
+    pub author: UserInfo,
+    pub committer: UserInfo,
+    pub message: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserInfo {
+    pub name: String,
+    pub date: String,
+}
+
+/// An entry of the pulls list or of search results, with just enough to
+/// pick PRs by.
+#[derive(Deserialize, Debug)]
+pub struct PullSummary {
+    pub number: u32,
+    /// Who opened the PR; missing for deleted accounts.
+    pub user: Option<User>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// Missing from search results and the issues list.
+    pub merged_at: Option<String>,
+}
+
+/// An entry of the issues list, which takes in PRs too.
+#[derive(Deserialize, Debug)]
+struct Issue {
+    #[serde(flatten)]
+    summary: PullSummary,
+    /// Only there for PRs.
+    pull_request: Option<serde::de::IgnoredAny>,
+}
+
+/// A review of a PR, one per time a reviewer submits one.
+#[derive(Deserialize, Debug)]
+pub struct Review {
+    /// Missing for deleted accounts.
+    pub user: Option<User>,
+    /// `APPROVED`, `CHANGES_REQUESTED`, `COMMENTED`, `DISMISSED` or `PENDING`.
+    pub state: String,
+}
+
+/// A comment on a line of a PR's diff.
+#[derive(Deserialize, Debug)]
+pub struct ReviewComment {
+    /// Missing for deleted accounts.
+    pub user: Option<User>,
+    pub path: String,
+    /// Line of the file the comment is on, `None` once the diff has moved
+    /// past it.
+    pub line: Option<u32>,
+    /// Line the comment was first made on.
+    pub original_line: Option<u32>,
+    pub body: String,
+    pub created_at: String,
+    pub html_url: String,
+}
+
+impl ReviewComment {
+    pub fn author(&self) -> &str {
+        login(self.user.as_ref())
+    }
+}
+
+/// A file changed by a PR or a commit.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ChangedFile {
+    pub filename: String,
+    pub status: String,
+    pub additions: u32,
+    pub deletions: u32,
+    /// Only there for renamed files.
+    pub previous_filename: Option<String>,
+}
+
+/// A PR to open.
+#[derive(Serialize, Debug)]
+pub struct NewPull<'a> {
+    pub title: &'a str,
+    /// The branch with the changes.
+    pub head: &'a str,
+    /// The branch they go into.
+    pub base: &'a str,
+    pub body: &'a str,
+}
+
+/// What the single-commit endpoint adds to a commit.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CommitDetails {
+    pub stats: CommitStats,
+    pub files: Vec<ChangedFile>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CommitStats {
+    pub additions: u32,
+    pub deletions: u32,
+}
+
+/// The lines a commit or PR adds and removes, and in how many files.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Diffstat {
+    pub additions: u32,
+    pub deletions: u32,
+    pub files: usize,
+}
+
+impl Diffstat {
+    /// `+12/-3`.
+    pub fn lines(&self) -> String {
+        format!("+{}/-{}", self.additions, self.deletions)
+    }
+}
+
+/// A comment on a PR's conversation, as opposed to one on its diff.
+#[derive(Deserialize, Debug)]
+pub struct IssueComment {
+    /// Missing for deleted accounts.
+    pub user: Option<User>,
+    pub body: String,
+    pub created_at: String,
+    pub html_url: String,
+}
+
+impl IssueComment {
+    pub fn author(&self) -> &str {
+        login(self.user.as_ref())
+    }
+}
+
+/// An entry of a PR's timeline. Events differ in their fields, so all but
+/// the most common are optional.
+#[derive(Deserialize, Debug)]
+pub struct TimelineItem {
+    pub event: Option<String>,
+    pub actor: Option<User>,
+    /// The author of a review, which has no `actor`.
+    pub user: Option<User>,
+    pub created_at: Option<String>,
+    /// When a review was submitted, as it has no `created_at`.
+    pub submitted_at: Option<String>,
+    /// State of a review, such as `approved`.
+    pub state: Option<String>,
+    pub requested_reviewer: Option<User>,
+    pub requested_team: Option<Team>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Team {
+    pub name: String,
+}
+
+/// A page of the check runs of a commit.
+#[derive(Deserialize, Debug)]
+struct CheckRuns {
+    check_runs: Vec<CheckRun>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckRun {
+    pub name: String,
+    /// `queued`, `in_progress` or `completed`.
+    pub status: String,
+    /// How a completed run ended, such as `success` or `failure`.
+    pub conclusion: Option<String>,
+}
+
+/// The combined commit status of a commit: the statuses that CI services
+/// other than GitHub Actions post.
+#[derive(Deserialize, Debug)]
+struct CombinedStatus {
+    statuses: Vec<StatusContext>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StatusContext {
+    /// Name of the status, such as `ci/jenkins`.
+    pub context: String,
+    /// `success`, `failure`, `error` or `pending`.
+    pub state: String,
+}
+
+/// An issue as the issues endpoint returns it, PRs included.
+#[derive(Deserialize, Debug)]
+pub struct IssueDetails {
+    pub number: u32,
+    pub title: String,
+    pub state: String,
+    pub html_url: String,
+}
+
+/// An entry of an organization's repositories.
+#[derive(Deserialize, Debug)]
+pub struct RepositorySummary {
+    pub name: String,
+    #[serde(default)]
+    pub topics: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Milestone {
+    number: u32,
+    title: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct User {
+    pub login: String,
+}
+
+/// The login of `user`, or `ghost` as on GitHub for accounts that are gone.
+pub fn login(user: Option<&User>) -> &str {
+    user.map_or("ghost", |user| user.login.as_str())
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Label {
+    pub name: String,
+}
+
+/// A page of issue search results.
+#[derive(Deserialize, Debug)]
+struct SearchResults {
+    total_count: u32,
+    items: Vec<SearchHit>,
+}
+
+/// An issue or PR matched by a search.
+#[derive(Deserialize, Debug)]
+pub struct SearchHit {
+    pub number: u32,
+    /// API URL of the repository the hit is in.
+    repository_url: String,
+    /// Only there for PRs.
+    pull_request: Option<serde::de::IgnoredAny>,
+}
+
+impl SearchHit {
+    pub fn is_pull_request(&self) -> bool {
+        self.pull_request.is_some()
+    }
+
+    /// The owner and name of the repository the hit is in.
+    pub fn repository(&self) -> Option<(&str, &str)> {
+        let mut segments = self.repository_url.rsplit('/');
+        let name = segments.next()?;
+        let owner = segments.next()?;
+        Some((owner, name))
+    }
+}
+
+/// A page of the compare API.
+#[derive(Deserialize, Debug)]
+struct Comparison {
+    commits: Vec<Commit>,
+}
+
+/// How the head of a comparison stands to its base.
+#[derive(Deserialize, Debug)]
+struct ComparisonStatus {
+    /// `ahead`, `behind`, `identical` or `diverged`.
+    status: String,
+}
+
+/// How requests are authenticated.
+pub enum Credentials {
+    /// Personal access tokens or any other tokens used as-is, rotated
+    /// between as their rate limits run down.
+    Tokens(TokenPool),
+    /// Short-lived installation tokens of a GitHub App.
+    App(AppAuth),
+    /// No credentials at all, for replaying a recording.
+    None,
+}
+
+/// Settings for [`GithubClient`].
+pub struct ClientOptions {
+    /// Root of the REST API, without a trailing slash.
+    pub api_url: String,
+    /// GraphQL endpoint; derived from `api_url` when not set.
+    pub graphql_url: Option<String>,
+    /// Value of the `X-GitHub-Api-Version` header.
+    pub api_version: String,
+    pub on_rate_limit: OnRateLimit,
+    /// How many times a request is retried after a 5xx or network error.
+    pub retries: u32,
+    /// Delay before the first retry; it doubles with every further attempt.
+    pub retry_backoff: Duration,
+    /// Revalidate responses stored on disk by earlier runs with ETags.
+    pub cache: bool,
+    /// Serve every GET from the cache and never touch the network.
+    pub offline: bool,
+    /// How long establishing a connection may take.
+    pub connect_timeout: Duration,
+    /// How long a whole request may take, from connecting to reading the body.
+    pub request_timeout: Duration,
+    /// Proxy for all requests. Without one, `HTTPS_PROXY`, `HTTP_PROXY` and
+    /// `NO_PROXY` are honored as usual.
+    pub proxy: Option<String>,
+    /// PEM file of extra root certificates to trust, e.g. those of an
+    /// SSL-inspecting proxy.
+    pub ca_cert: Option<PathBuf>,
+    /// Directory every response is saved to, for replaying later.
+    pub record: Option<PathBuf>,
+    /// Directory of recorded responses to serve instead of the network.
+    pub replay: Option<PathBuf>,
+}
+
+/// A GitHub REST API client. A single instance is shared by every request so
+/// that connections and TLS sessions are reused.
+pub struct GithubClient {
+    client: reqwest::Client,
+    credentials: Credentials,
+    options: ClientOptions,
+    graphql_url: String,
+    cache: Option<Cache>,
+    record: Option<Fixtures>,
+    replay: Option<Fixtures>,
+}
+
+/// The parts of a GET response the client needs, whether it came from the
+/// network or from the cache.
+struct Page {
+    body: String,
+    next: Option<String>,
+}
+
+impl GithubClient {
+    pub fn new(
+        credentials: Credentials,
+        mut options: ClientOptions,
+    ) -> Result<Self, Box<dyn Error>> {
+        reqwest::Url::parse(&options.api_url)
+            .map_err(|err| format!("invalid API URL {}: {}", options.api_url, err))?;
+        options.api_url = options.api_url.trim_end_matches('/').to_string();
+        let graphql_url = options
+            .graphql_url
+            .clone()
+            .unwrap_or_else(|| graphql_url(&options.api_url));
+        reqwest::Url::parse(&graphql_url)
+            .map_err(|err| format!("invalid GraphQL URL {}: {}", graphql_url, err))?;
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("rust-client"));
+        headers.insert(
+            "X-GitHub-Api-Version",
+            HeaderValue::from_str(&options.api_version)
+                .map_err(|_| format!("invalid API version {:?}", options.api_version))?,
+        );
+
+        let mut builder = reqwest::Client::builder()
+            .default_headers(headers)
+            .connect_timeout(options.connect_timeout)
+            .timeout(options.request_timeout);
+        if let Some(proxy) = &options.proxy {
+            let proxy = Proxy::all(proxy)
+                .map_err(|err| format!("invalid proxy URL {}: {}", proxy, err))?
+                .no_proxy(NoProxy::from_env());
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &options.ca_cert {
+            let pem = std::fs::read(path)
+                .map_err(|err| format!("can't read {}: {}", path.display(), err))?;
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .map_err(|err| format!("invalid certificate in {}: {}", path.display(), err))?;
+            if certificates.is_empty() {
+                return Err(format!("no certificates found in {}", path.display()).into());
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        let client = builder.build()?;
+        let cache = if options.cache || options.offline {
+            Cache::open()
+        } else {
+            None
+        };
+        let record = options.record.clone().map(Fixtures::new);
+        let replay = options.replay.clone().map(Fixtures::new);
+        Ok(GithubClient {
+            client,
+            credentials,
+            options,
+            graphql_url,
+            cache,
+            record,
+            replay,
+        })
+    }
+
+    pub async fn pull_request(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: u32,
+    ) -> Result<PullRequest, Box<dyn Error>> {
+        let url = pull_request_url(&self.options.api_url, owner, repo, pr_number);
+        self.get(&url).await
+    }
+
+    /// The number of the most recently opened PR, open or not, or `None` if
+    /// the repository has no PRs.
+    pub async fn newest_pull_number(
+        &self,
+        owner: &str,
+        repo: &str,
+    ) -> Result<Option<u32>, Box<dyn Error>> {
+        let url = newest_pull_url(&self.options.api_url, owner, repo);
+        let pulls: Vec<PullSummary> = self.get(&url).await?;
+        Ok(pulls.first().map(|pull| pull.number))
+    }
+
+    /// The number of the milestone titled `title`, open or closed, or `None`
+    /// if the repository has no such milestone.
+    pub async fn milestone_number(
+        &self,
+        owner: &str,
+        repo: &str,
+        title: &str,
+    ) -> Result<Option<u32>, Box<dyn Error>> {
+        let url = milestones_url(&self.options.api_url, owner, repo);
+        let pages = self.get_pages::<Vec<Milestone>>(url).await?;
+        Ok(pages
+            .into_iter()
+            .flatten()
+            .find(|milestone| milestone.title == title)
+            .map(|milestone| milestone.number))
+    }
+
+    /// All PRs attached to a milestone, open or closed.
+    pub async fn milestone_pulls(
+        &self,
+        owner: &str,
+        repo: &str,
+        milestone: u32,
+    ) -> Result<Vec<PullSummary>, Box<dyn Error>> {
+        let url = milestone_issues_url(&self.options.api_url, owner, repo, milestone);
+        let pages = self.get_pages::<Vec<Issue>>(url).await?;
+        Ok(pages
+            .into_iter()
+            .flatten()
+            .filter(|issue| issue.pull_request.is_some())
+            .map(|issue| issue.summary)
+            .collect())
+    }
+
+    /// All repositories of an organization.
+    pub async fn org_repositories(
+        &self,
+        org: &str,
+    ) -> Result<Vec<RepositorySummary>, Box<dyn Error>> {
+        let url = org_repos_url(&self.options.api_url, org);
+        let pages = self.get_pages::<Vec<RepositorySummary>>(url).await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// All open PRs, newest first.
+    pub async fn open_pulls(
+        &self,
+        owner: &str,
+        repo: &str,
+    ) -> Result<Vec<PullSummary>, Box<dyn Error>> {
+        let url = open_pulls_url(&self.options.api_url, owner, repo);
+        let pages = self.get_pages::<Vec<PullSummary>>(url).await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// The PRs that contain a commit, or were merged as it.
+    pub async fn commit_pulls(
+        &self,
+        owner: &str,
+        repo: &str,
+        sha: &str,
+    ) -> Result<Vec<PullSummary>, Box<dyn Error>> {
+        let url = commit_pulls_url(&self.options.api_url, owner, repo, sha);
+        let pages = self.get_pages::<Vec<PullSummary>>(url).await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// The issues and PRs matching a search query, in the order the search
+    /// returns them.
+    pub async fn search(&self, query: &str) -> Result<Vec<SearchHit>, Box<dyn Error>> {
+        let pages = self
+            .get_pages::<SearchResults>(search_url(&self.options.api_url, query))
+            .await?;
+        if let Some(total) = pages.first().map(|page| page.total_count) {
+            if total > SEARCH_LIMIT {
+                warn!(
+                    "Search matched {} results, but only the first {} can be listed",
+                    total, SEARCH_LIMIT
+                );
+            }
+        }
+        Ok(pages.into_iter().flat_map(|page| page.items).collect())
+    }
+
+    /// Every review submitted on a PR, oldest first.
+    pub async fn pull_reviews(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: u32,
+    ) -> Result<Vec<Review>, Box<dyn Error>> {
+        let url = pull_reviews_url(&self.options.api_url, owner, repo, pr_number);
+        let pages = self.get_pages::<Vec<Review>>(url).await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// Every comment on the diff of a PR, oldest first.
+    pub async fn pull_review_comments(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: u32,
+    ) -> Result<Vec<ReviewComment>, Box<dyn Error>> {
+        let url = pull_review_comments_url(&self.options.api_url, owner, repo, pr_number);
+        let pages = self.get_pages::<Vec<ReviewComment>>(url).await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// The files a PR changes; GitHub lists no more than 3000.
+    pub async fn pull_files(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: u32,
+    ) -> Result<Vec<ChangedFile>, Box<dyn Error>> {
+        let url = pull_files_url(&self.options.api_url, owner, repo, pr_number);
+        let pages = self.get_pages::<Vec<ChangedFile>>(url).await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// Every comment on the conversation of a PR, oldest first.
+    pub async fn issue_comments(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: u32,
+    ) -> Result<Vec<IssueComment>, Box<dyn Error>> {
+        let url = issue_comments_url(&self.options.api_url, owner, repo, pr_number);
+        let pages = self.get_pages::<Vec<IssueComment>>(url).await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// Everything that happened on a PR, oldest first.
+    pub async fn timeline(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: u32,
+    ) -> Result<Vec<TimelineItem>, Box<dyn Error>> {
+        let url = timeline_url(&self.options.api_url, owner, repo, pr_number);
+        let pages = self.get_pages::<Vec<TimelineItem>>(url).await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// A single commit with the files it changes; GitHub lists no more
+    /// than 300.
+    pub async fn commit(
+        &self,
+        owner: &str,
+        repo: &str,
+        sha: &str,
+    ) -> Result<CommitDetails, Box<dyn Error>> {
+        self.get(&commit_url(&self.options.api_url, owner, repo, sha))
+            .await
+    }
+
+    /// The check runs of a commit, such as those of GitHub Actions.
+    pub async fn check_runs(
+        &self,
+        owner: &str,
+        repo: &str,
+        sha: &str,
+    ) -> Result<Vec<CheckRun>, Box<dyn Error>> {
+        let url = check_runs_url(&self.options.api_url, owner, repo, sha);
+        let pages = self.get_pages::<CheckRuns>(url).await?;
+        Ok(pages.into_iter().flat_map(|page| page.check_runs).collect())
+    }
+
+    /// The latest commit status of each context on a commit.
+    pub async fn commit_statuses(
+        &self,
+        owner: &str,
+        repo: &str,
+        sha: &str,
+    ) -> Result<Vec<StatusContext>, Box<dyn Error>> {
+        let url = commit_status_url(&self.options.api_url, owner, repo, sha);
+        let pages = self.get_pages::<CombinedStatus>(url).await?;
+        Ok(pages.into_iter().flat_map(|page| page.statuses).collect())
+    }
+
+    /// A single issue.
+    pub async fn issue(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+    ) -> Result<IssueDetails, Box<dyn Error>> {
+        self.get(&issue_url(&self.options.api_url, owner, repo, number))
+            .await
+    }
+
+    /// Fetches every commit of a PR, going through the compare API when the
+    /// PR has more commits than the pulls endpoint will return.
+    pub async fn pull_commits(
+        &self,
+        owner: &str,
+        repo: &str,
+        pull: &PullRequest,
+    ) -> Result<Vec<Commit>, Box<dyn Error>> {
+        if pull.commits > PULL_COMMITS_LIMIT {
+            return self
+                .compare_commits(owner, repo, &pull.base.sha, &pull.head.sha)
+                .await;
+        }
+        let url = pull_commits_url(&self.options.api_url, owner, repo, pull.number);
+        let pages = self.get_pages::<Vec<Commit>>(url).await?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// Fetches the commits between `base` and `head` through the compare API,
+    /// which unlike the pulls endpoint pages past 250 commits.
+    pub async fn compare_commits(
+        &self,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+    ) -> Result<Vec<Commit>, Box<dyn Error>> {
+        let url = compare_url(&self.options.api_url, owner, repo, base, head);
+        let pages = self.get_pages::<Comparison>(url).await?;
+        Ok(pages.into_iter().flat_map(|page| page.commits).collect())
+    }
+
+    /// How `head` stands to `base`: `ahead`, `behind`, `identical` or
+    /// `diverged`.
+    pub async fn compare_status(
+        &self,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let url = compare_status_url(&self.options.api_url, owner, repo, base, head);
+        let comparison: ComparisonStatus = self.get(&url).await?;
+        Ok(comparison.status)
+    }
+
+    /// The REST API root requests go to.
+    pub fn api_url(&self) -> &str {
+        &self.options.api_url
+    }
+
+    /// Where GraphQL queries are sent.
+    pub fn graphql_url(&self) -> &str {
+        &self.graphql_url
+    }
+
+    /// Sends a GraphQL request body and returns the decoded response.
+    pub async fn post_graphql<T: DeserializeOwned>(
+        &self,
+        body: &serde_json::Value,
+    ) -> Result<T, Box<dyn Error>> {
+        if self.options.offline {
+            return Err("GraphQL responses aren't cached; use --api rest with --offline".into());
+        }
+        let request = self.client.post(&self.graphql_url).json(body);
+        let response = self.send(request).await?;
+        let status = response.status();
+        let body = response.text().await.map_err(error::Error::Network)?;
+        if !status.is_success() {
+            return Err(error::Error::from_response(&self.graphql_url, status, &body).into());
+        }
+        Ok(decode(&self.graphql_url, &body)?)
+    }
+
+    /// The diff of a PR as a whole.
+    pub async fn pull_diff(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: u32,
+    ) -> Result<String, Box<dyn Error>> {
+        let url = pull_request_url(&self.options.api_url, owner, repo, pr_number);
+        self.get_text(&url, DIFF_MEDIA_TYPE).await
+    }
+
+    /// The diff of a single commit.
+    pub async fn commit_diff(
+        &self,
+        owner: &str,
+        repo: &str,
+        sha: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let url = commit_url(&self.options.api_url, owner, repo, sha);
+        self.get_text(&url, DIFF_MEDIA_TYPE).await
+    }
+
+    /// A single commit as a patch for `git am`.
+    pub async fn commit_patch(
+        &self,
+        owner: &str,
+        repo: &str,
+        sha: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let url = commit_url(&self.options.api_url, owner, repo, sha);
+        self.get_text(&url, PATCH_MEDIA_TYPE).await
+    }
+
+    /// GETs `url` in another representation than JSON, such as a diff.
+    /// These aren't cached, as the cache goes by URL alone.
+    async fn get_text(&self, url: &str, media_type: &str) -> Result<String, Box<dyn Error>> {
+        if self.options.offline {
+            return Err(format!("{} isn't cached; fetch it without --offline", media_type).into());
+        }
+        let request = self.client.get(url).header(ACCEPT, media_type);
+        let response = self.send(request).await?;
+        let status = response.status();
+        let body = response.text().await.map_err(error::Error::Network)?;
+        if !status.is_success() {
+            return Err(error::Error::from_response(url, status, &body).into());
+        }
+        Ok(body)
+    }
+
+    /// Opens a PR merging `head` into `base`.
+    pub async fn create_pull(
+        &self,
+        owner: &str,
+        repo: &str,
+        pull: &NewPull<'_>,
+    ) -> Result<PullRequest, Box<dyn Error>> {
+        let url = format!("{}/repos/{}/{}/pulls", self.options.api_url, owner, repo);
+        self.post(&url, pull).await
+    }
+
+    /// POSTs `body` as JSON to `url` and decodes the response.
+    async fn post<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &impl Serialize,
+    ) -> Result<T, Box<dyn Error>> {
+        if self.options.offline {
+            return Err(format!("can't POST to {} with --offline", url).into());
+        }
+        let response = self.send(self.client.post(url).json(body)).await?;
+        let status = response.status();
+        let text = response.text().await.map_err(error::Error::Network)?;
+        if !status.is_success() {
+            return Err(error::Error::from_response(url, status, &text).into());
+        }
+        Ok(decode(url, &text)?)
+    }
+
+    /// GETs `path` under the API root and returns the response whatever its
+    /// status, for callers interested in more than the body.
+    pub async fn get_response(&self, path: &str) -> Result<Response, Box<dyn Error>> {
+        let url = format!("{}{}", self.options.api_url, path);
+        self.send(self.client.get(url)).await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: &str) -> Result<T, Box<dyn Error>> {
+        let page = self.get_page(url).await?;
+        Ok(decode(url, &page.body)?)
+    }
+
+    /// GETs `url`, sending the ETag of a cached copy so that an unchanged
+    /// resource comes back as a `304 Not Modified`, which doesn't count
+    /// against the rate limit. Offline, the cached copy is all there is.
+    async fn get_page(&self, url: &str) -> Result<Page, Box<dyn Error>> {
+        let cached = self.cache.as_ref().and_then(|cache| cache.get(url));
+        if self.options.offline {
+            let entry = cached.ok_or_else(|| {
+                format!(
+                    "{} is not in the cache; fetch it once without --offline",
+                    url
+                )
+            })?;
+            debug!("{} served from the cache", url);
+            return Ok(Page {
+                next: entry.link.as_deref().and_then(next_page),
+                body: entry.body,
+            });
+        }
+        let mut request = self.client.get(url);
+        if let Some(etag) = cached.as_ref().and_then(|entry| entry.etag.as_ref()) {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = self.send(request).await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(entry) = cached {
+                debug!("{} not modified; using the cached copy", url);
+                return Ok(Page {
+                    body: entry.body,
+                    next: entry.link.as_deref().and_then(next_page),
+                });
+            }
+        }
+
+        let link = header_string(&response, LINK);
+        let etag = header_string(&response, ETAG);
+        let status = response.status();
+        let body = response.text().await.map_err(error::Error::Network)?;
+        if !status.is_success() {
+            return Err(error::Error::from_response(url, status, &body).into());
+        }
+        if let Some(cache) = &self.cache {
+            cache.put(&cache::Entry {
+                url: url.to_string(),
+                etag,
+                link: link.clone(),
+                body: body.clone(),
+                fetched_at: chrono::Utc::now().to_rfc3339(),
+            });
+        }
+        Ok(Page {
+            body,
+            next: link.as_deref().and_then(next_page),
+        })
+    }
+
+    /// Sends a request, waiting out or failing on an exhausted rate limit
+    /// according to `--on-rate-limit`, sleeping through secondary rate
+    /// limits for as long as `Retry-After` asks, and retrying transient
+    /// failures with exponential backoff. With `--replay`, the recorded
+    /// response is all there is.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, Box<dyn Error>> {
+        if let Some(replay) = &self.replay {
+            let request = request.build().map_err(error::Error::Network)?;
+            debug!("{} {} (replayed)", request.method(), request.url());
+            return replay.replay(&request);
+        }
+        let mut secondary_retries = 0;
+        let mut failures = 0;
+        loop {
+            let (authorization, pooled) = self.authorization().await?;
+            let attempt = request
+                .try_clone()
+                .ok_or("request body cannot be retried")?
+                .header(AUTHORIZATION, authorization)
+                .build()
+                .map_err(error::Error::Network)?;
+            let (method, url) = (attempt.method().clone(), attempt.url().clone());
+            debug!("{} {}", method, url);
+            // Kept to name the recording, since executing consumes the request.
+            let recorded = match &self.record {
+                Some(_) => attempt.try_clone(),
+                None => None,
+            };
+            let started = Instant::now();
+            let result = self.client.execute(attempt).await;
+            log_response(&method, &url, started, &result);
+            if let (Ok(response), Credentials::Tokens(pool), Some(index)) =
+                (&result, &self.credentials, pooled)
+            {
+                pool.record(index, response);
+            }
+            let response = match result {
+                Ok(response)
+                    if response.status().is_server_error() && failures < self.options.retries =>
+                {
+                    failures += 1;
+                    self.backoff(failures, &response.status().to_string()).await;
+                    continue;
+                }
+                Err(err) if is_transient(&err) && failures < self.options.retries => {
+                    failures += 1;
+                    self.backoff(failures, &err.to_string()).await;
+                    continue;
+                }
+                result => result.map_err(error::Error::Network)?,
+            };
+            if let Some(wait) = ratelimit::retry_after(&response) {
+                if secondary_retries < SECONDARY_LIMIT_RETRIES {
+                    secondary_retries += 1;
+                    warn!(
+                        "GitHub secondary rate limit hit; retrying in {}s",
+                        wait.as_secs()
+                    );
+                    tokio::time::sleep(wait).await;
+                    continue;
+                }
+            }
+            let Some(reset) = ratelimit::exhausted(&response) else {
+                return match (&self.record, &recorded) {
+                    (Some(record), Some(request)) => record.record(request, response).await,
+                    _ => Ok(response),
+                };
+            };
+            if let Credentials::Tokens(pool) = &self.credentials {
+                if pool.has_spare() {
+                    info!("GitHub API rate limit exceeded; switching to another token");
+                    continue;
+                }
+            }
+            match self.options.on_rate_limit {
+                OnRateLimit::Fail => {
+                    return Err(error::Error::RateLimited {
+                        reset: Some(reset),
+                        message: String::new(),
+                    }
+                    .into());
+                }
+                OnRateLimit::Wait => {
+                    let wait = (reset - chrono::Utc::now()).to_std().unwrap_or_default()
+                        + Duration::from_secs(1);
+                    warn!(
+                        "GitHub API rate limit exceeded; waiting until {} ({}s)",
+                        ratelimit::describe_reset(reset),
+                        wait.as_secs()
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// The `Authorization` header for the next request, along with the
+    /// index of the pooled token it uses.
+    async fn authorization(&self) -> Result<(HeaderValue, Option<usize>), Box<dyn Error>> {
+        let (token, index) = match &self.credentials {
+            Credentials::Tokens(pool) => {
+                let (index, token) = pool.pick();
+                (token.to_string(), Some(index))
+            }
+            Credentials::App(app) => (app.token(&self.client, &self.options.api_url).await?, None),
+            Credentials::None => return Err("no GitHub credentials".into()),
+        };
+        let header = HeaderValue::from_str(&format!("token {}", token))
+            .map_err(|_| "the GitHub token contains characters not allowed in an HTTP header")?;
+        Ok((header, index))
+    }
+
+    /// Sleeps before retry number `attempt`: the base delay doubled for each
+    /// earlier attempt, plus up to one base delay of random jitter so that
+    /// concurrent requests don't retry in lockstep.
+    async fn backoff(&self, attempt: u32, reason: &str) {
+        let base = self.options.retry_backoff;
+        let delay = base * 2u32.saturating_pow(attempt - 1) + base.mul_f64(rand::random::<f64>());
+        warn!(
+            "Request failed ({}); retry {}/{} in {:.1}s",
+            redact::redact(reason),
+            attempt,
+            self.options.retries,
+            delay.as_secs_f64()
+        );
+        tokio::time::sleep(delay).await;
+    }
+
+    /// Fetches every page of a paginated endpoint, following the `next` link
+    /// until the last page.
+    async fn get_pages<T: DeserializeOwned>(&self, url: String) -> Result<Vec<T>, Box<dyn Error>> {
+        let mut url = Some(url);
+        let mut pages = Vec::new();
+        while let Some(page_url) = url {
+            let page = self.get_page(&page_url).await?;
+            url = page.next;
+            pages.push(decode(&page_url, &page.body)?);
+        }
+        Ok(pages)
+    }
+}
+
+pub fn pull_request_url(api_url: &str, owner: &str, repo: &str, pr_number: u32) -> String {
+    format!("{}/repos/{}/{}/pulls/{}", api_url, owner, repo, pr_number)
+}
+
+/// The pulls list, narrowed down to the most recently opened PR.
+pub fn newest_pull_url(api_url: &str, owner: &str, repo: &str) -> String {
+    format!(
+        "{}/repos/{}/{}/pulls?state=all&sort=created&direction=desc&per_page=1",
+        api_url, owner, repo
+    )
+}
+
+/// First page of the issue search results for `query`.
+pub fn search_url(api_url: &str, query: &str) -> String {
+    let mut url = reqwest::Url::parse(&format!("{}/search/issues", api_url))
+        .expect("the API URL was validated");
+    url.query_pairs_mut()
+        .append_pair("q", query)
+        .append_pair("per_page", "100");
+    url.to_string()
+}
+
+/// The PRs that contain a commit.
+pub fn commit_pulls_url(api_url: &str, owner: &str, repo: &str, sha: &str) -> String {
+    format!(
+        "{}/repos/{}/{}/commits/{}/pulls?per_page=100",
+        api_url, owner, repo, sha
+    )
+}
+
+/// First page of the open PRs.
+pub fn open_pulls_url(api_url: &str, owner: &str, repo: &str) -> String {
+    format!(
+        "{}/repos/{}/{}/pulls?state=open&per_page=100",
+        api_url, owner, repo
+    )
+}
+
+/// First page of an organization's repositories.
+pub fn org_repos_url(api_url: &str, org: &str) -> String {
+    format!("{}/orgs/{}/repos?per_page=100", api_url, org)
+}
+
+/// First page of the milestones, open and closed.
+pub fn milestones_url(api_url: &str, owner: &str, repo: &str) -> String {
+    format!(
+        "{}/repos/{}/{}/milestones?state=all&per_page=100",
+        api_url, owner, repo
+    )
+}
+
+/// First page of the issues and PRs attached to a milestone.
+pub fn milestone_issues_url(api_url: &str, owner: &str, repo: &str, milestone: u32) -> String {
+    format!(
+        "{}/repos/{}/{}/issues?milestone={}&state=all&per_page=100",
+        api_url, owner, repo, milestone
+    )
+}
+
+/// First page of a PR's commits; later pages are found through `Link`.
+pub fn pull_commits_url(api_url: &str, owner: &str, repo: &str, pr_number: u32) -> String {
+    format!(
+        "{}/repos/{}/{}/pulls/{}/commits?per_page=100",
+        api_url, owner, repo, pr_number
+    )
+}
+
+pub fn pull_reviews_url(api_url: &str, owner: &str, repo: &str, pr_number: u32) -> String {
+    format!(
+        "{}/repos/{}/{}/pulls/{}/reviews?per_page=100",
+        api_url, owner, repo, pr_number
+    )
+}
+
+pub fn pull_files_url(api_url: &str, owner: &str, repo: &str, pr_number: u32) -> String {
+    format!(
+        "{}/repos/{}/{}/pulls/{}/files?per_page=100",
+        api_url, owner, repo, pr_number
+    )
+}
+
+pub fn pull_review_comments_url(api_url: &str, owner: &str, repo: &str, pr_number: u32) -> String {
+    format!(
+        "{}/repos/{}/{}/pulls/{}/comments?per_page=100",
+        api_url, owner, repo, pr_number
+    )
+}
+
+pub fn issue_comments_url(api_url: &str, owner: &str, repo: &str, pr_number: u32) -> String {
+    format!(
+        "{}/repos/{}/{}/issues/{}/comments?per_page=100",
+        api_url, owner, repo, pr_number
+    )
+}
+
+pub fn timeline_url(api_url: &str, owner: &str, repo: &str, pr_number: u32) -> String {
+    format!(
+        "{}/repos/{}/{}/issues/{}/timeline?per_page=100",
+        api_url, owner, repo, pr_number
+    )
+}
+
+/// The compare API, asking for as few of the commits as it gives.
+pub fn compare_status_url(
+    api_url: &str,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+) -> String {
+    format!(
+        "{}/repos/{}/{}/compare/{}...{}?per_page=1",
+        api_url, owner, repo, base, head
+    )
+}
+
+/// First page of the commits `head` has and `base` lacks.
+pub fn compare_url(api_url: &str, owner: &str, repo: &str, base: &str, head: &str) -> String {
+    format!(
+        "{}/repos/{}/{}/compare/{}...{}?per_page=100",
+        api_url, owner, repo, base, head
+    )
+}
+
+pub fn commit_url(api_url: &str, owner: &str, repo: &str, sha: &str) -> String {
+    format!("{}/repos/{}/{}/commits/{}", api_url, owner, repo, sha)
+}
+
+pub fn check_runs_url(api_url: &str, owner: &str, repo: &str, sha: &str) -> String {
+    format!(
+        "{}/repos/{}/{}/commits/{}/check-runs?per_page=100",
+        api_url, owner, repo, sha
+    )
+}
+
+pub fn commit_status_url(api_url: &str, owner: &str, repo: &str, sha: &str) -> String {
+    format!(
+        "{}/repos/{}/{}/commits/{}/status?per_page=100",
+        api_url, owner, repo, sha
+    )
+}
+
+pub fn issue_url(api_url: &str, owner: &str, repo: &str, number: u32) -> String {
+    format!("{}/repos/{}/{}/issues/{}", api_url, owner, repo, number)
+}
+
+/// The GraphQL endpoint that goes with a REST API root: `/graphql` on
+/// api.github.com, but `/api/graphql` next to GitHub Enterprise Server's
+/// `/api/v3`.
+pub fn graphql_url(api_url: &str) -> String {
+    match api_url.strip_suffix("/api/v3") {
+        Some(host) => format!("{}/api/graphql", host),
+        None => format!("{}/graphql", api_url),
+    }
+}
+
+fn decode<T: DeserializeOwned>(url: &str, body: &str) -> Result<T, error::Error> {
+    serde_json::from_str(body).map_err(|source| error::Error::Decode {
+        url: url.to_string(),
+        source,
+    })
+}
+
+/// Logs how a request went: its status, how long it took and what is left
+/// of the rate limit.
+fn log_response(
+    method: &reqwest::Method,
+    url: &reqwest::Url,
+    started: Instant,
+    result: &reqwest::Result<Response>,
+) {
+    let elapsed = started.elapsed();
+    match result {
+        Ok(response) => match ratelimit::remaining(response) {
+            Some((remaining, reset)) => debug!(
+                "{} {} -> {} in {:.2?} (rate limit: {} left, resets at {})",
+                method,
+                url,
+                response.status(),
+                elapsed,
+                remaining,
+                ratelimit::describe_reset(reset)
+            ),
+            None => debug!(
+                "{} {} -> {} in {:.2?}",
+                method,
+                url,
+                response.status(),
+                elapsed
+            ),
+        },
+        Err(err) => debug!(
+            "{} {} failed after {:.2?}: {}",
+            method,
+            url,
+            elapsed,
+            redact::redact(&err.to_string())
+        ),
+    }
+}
+
+/// Network-level failures worth retrying, as opposed to e.g. invalid URLs.
+fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout() || err.is_request()
+}
+
+fn header_string(response: &Response, name: reqwest::header::HeaderName) -> Option<String> {
+    Some(response.headers().get(name)?.to_str().ok()?.to_string())
+}
+
+/// Extracts the `rel="next"` URL from a `Link` response header.
+fn next_page(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == r#"rel="next""#)
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
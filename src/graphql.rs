@@ -0,0 +1,577 @@
+//! Batched PR fetching through the GraphQL API: one query returns the
+//! titles and first page of commits for many PRs, instead of two or more
+//! REST calls per PR.
+
+use crate::error;
+use crate::github::{Commit, CommitInfo, GitRef, GithubClient, Label, PullRequest, User, UserInfo};
+use crate::issues::LinkedIssue;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// How many PRs are requested in a single query. GitHub limits the number of
+/// nodes a query may touch, so very large batches get rejected.
+pub const BATCH_SIZE: usize = 20;
+
+/// PR connections over GraphQL stop at the same 250 commits as REST.
+const COMMITS_LIMIT: u32 = 250;
+
+const COMMIT_FIELDS: &str = "
+fragment commitFields on PullRequestCommitConnection {
+  pageInfo { hasNextPage endCursor }
+  nodes {
+    commit {
+      oid
+      url
+      message
+      author { name date }
+      committer { name date }
+      parents { totalCount }
+    }
+  }
+}";
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<ResponseError>,
+}
+
+#[derive(Deserialize)]
+struct ResponseError {
+    message: String,
+    /// Such as `NOT_FOUND`; not every error has one.
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    /// Where in `data` the error happened, as field names and indexes.
+    #[serde(default)]
+    path: Vec<serde_json::Value>,
+}
+
+/// A PR of a batch, or why it couldn't be fetched.
+pub type BatchResult = Result<(PullRequest, Vec<Commit>), Box<dyn Error>>;
+
+#[derive(Deserialize)]
+struct BatchData {
+    repository: HashMap<String, Option<PullNode>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PullNode {
+    number: u32,
+    title: String,
+    url: String,
+    author: Option<User>,
+    /// `OPEN`, `CLOSED` or `MERGED`.
+    state: String,
+    created_at: String,
+    merged_at: Option<String>,
+    merge_commit: Option<Oid>,
+    body: String,
+    /// `MERGEABLE`, `CONFLICTING` or `UNKNOWN`.
+    mergeable: String,
+    merge_state_status: Option<String>,
+    labels: Labels,
+    /// Only asked for with `closing_issues`.
+    closing_issues_references: Option<ClosingIssues>,
+    is_draft: bool,
+    base_ref_name: String,
+    base_ref_oid: String,
+    head_ref_name: String,
+    head_ref_oid: String,
+    total: TotalCount,
+    commits: CommitConnection,
+}
+
+#[derive(Deserialize)]
+struct Oid {
+    oid: String,
+}
+
+#[derive(Deserialize)]
+struct Labels {
+    nodes: Vec<Label>,
+}
+
+#[derive(Deserialize)]
+struct ClosingIssues {
+    nodes: Vec<ClosingIssue>,
+}
+
+#[derive(Deserialize)]
+struct ClosingIssue {
+    number: u32,
+    title: String,
+    /// `OPEN` or `CLOSED`.
+    state: String,
+    url: String,
+    repository: NameWithOwner,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NameWithOwner {
+    name_with_owner: String,
+}
+
+impl From<ClosingIssue> for LinkedIssue {
+    fn from(issue: ClosingIssue) -> Self {
+        LinkedIssue {
+            repository: issue.repository.name_with_owner,
+            number: issue.number,
+            title: issue.title,
+            state: issue.state.to_lowercase(),
+            url: issue.url,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TotalCount {
+    total_count: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommitConnection {
+    page_info: PageInfo,
+    nodes: Vec<CommitNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CommitNode {
+    commit: GitCommit,
+}
+
+#[derive(Deserialize)]
+struct GitCommit {
+    oid: String,
+    url: String,
+    message: String,
+    author: Option<GitActor>,
+    committer: Option<GitActor>,
+    parents: Parents,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Parents {
+    total_count: usize,
+}
+
+#[derive(Deserialize)]
+struct GitActor {
+    name: Option<String>,
+    date: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PageData {
+    repository: PageRepository,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageRepository {
+    pull_request: PageNode,
+}
+
+#[derive(Deserialize)]
+struct PageNode {
+    commits: CommitConnection,
+}
+
+impl PullNode {
+    /// The PR in its REST shape, and the first page of its commits.
+    fn into_parts(self) -> (PullRequest, CommitConnection) {
+        let pull = PullRequest {
+            number: self.number,
+            title: self.title,
+            html_url: self.url,
+            user: self.author,
+            // REST counts merged PRs as closed and tells them apart by
+            // `merged_at`.
+            state: match self.state.as_str() {
+                "OPEN" => "open",
+                _ => "closed",
+            }
+            .to_string(),
+            created_at: self.created_at,
+            merged: self.state == "MERGED",
+            merged_at: self.merged_at,
+            merge_commit_sha: self.merge_commit.map(|commit| commit.oid),
+            mergeable: match self.mergeable.as_str() {
+                "MERGEABLE" => Some(true),
+                "CONFLICTING" => Some(false),
+                _ => None,
+            },
+            mergeable_state: self.merge_state_status.map(|state| state.to_lowercase()),
+            body: (!self.body.is_empty()).then_some(self.body),
+            labels: self.labels.nodes,
+            draft: self.is_draft,
+            closing_issues: self
+                .closing_issues_references
+                .map(|issues| issues.nodes.into_iter().map(LinkedIssue::from).collect()),
+            commits: self.total.total_count,
+            base: GitRef {
+                name: self.base_ref_name,
+                sha: self.base_ref_oid,
+            },
+            head: GitRef {
+                name: self.head_ref_name,
+                sha: self.head_ref_oid,
+            },
+        };
+        (pull, self.commits)
+    }
+}
+
+impl From<GitActor> for UserInfo {
+    fn from(actor: GitActor) -> Self {
+        UserInfo {
+            name: actor.name.unwrap_or_default(),
+            date: actor.date.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<GitCommit> for Commit {
+    fn from(commit: GitCommit) -> Self {
+        let actor = |actor: Option<GitActor>| {
+            actor.map(UserInfo::from).unwrap_or(UserInfo {
+                name: String::new(),
+                date: String::new(),
+            })
+        };
+        Commit {
+            sha: commit.oid,
+            html_url: commit.url,
+            commit: CommitInfo {
+                author: actor(commit.author),
+                committer: actor(commit.committer),
+                message: commit.message,
+            },
+            parents: vec![IgnoredAny; commit.parents.total_count],
+            checks: None,
+            files: None,
+            stats: None,
+            patch: None,
+            landed: None,
+            also_in: None,
+        }
+    }
+}
+
+impl GithubClient {
+    /// Fetches the given PRs and all of their commits, in the order given.
+    /// With `closing_issues`, also the issues each PR closes; GitHub
+    /// Enterprise Server before 3.3 doesn't know of them.
+    pub async fn pull_requests_batch(
+        &self,
+        owner: &str,
+        repo: &str,
+        numbers: &[u32],
+        closing_issues: bool,
+    ) -> Result<Vec<(u32, BatchResult)>, Box<dyn Error>> {
+        let closing_issues = if closing_issues {
+            "closingIssuesReferences(first: 25) {
+               nodes { number title state url repository { nameWithOwner } }
+             }"
+        } else {
+            ""
+        };
+        // An alias can only be asked for once.
+        let mut unique: Vec<u32> = Vec::with_capacity(numbers.len());
+        for &number in numbers {
+            if !unique.contains(&number) {
+                unique.push(number);
+            }
+        }
+        let fields: String = unique
+            .iter()
+            .map(|number| {
+                format!(
+                    "pr{number}: pullRequest(number: {number}) {{
+                       number title url author {{ login }} state createdAt mergedAt mergeCommit {{ oid }} body isDraft
+                       mergeable mergeStateStatus
+                       labels(first: 100) {{ nodes {{ name }} }}
+                       {closing_issues}
+                       baseRefName baseRefOid headRefName headRefOid
+                       total: commits {{ totalCount }}
+                       commits(first: 100) {{ ...commitFields }}
+                     }}\n"
+                )
+            })
+            .collect();
+        let query = format!(
+            "query($owner: String!, $repo: String!) {{
+               repository(owner: $owner, name: $repo) {{ {fields} }}
+             }}
+             {COMMIT_FIELDS}"
+        );
+        let (data, errors) = self
+            .query_partial::<BatchData>(&query, json!({ "owner": owner, "repo": repo }))
+            .await?;
+        let mut failed = pr_errors(errors, &unique)?;
+        let mut data = data.ok_or("GraphQL response contained no data")?;
+
+        let mut results = Vec::with_capacity(unique.len());
+        for number in unique {
+            let alias = format!("pr{}", number);
+            let node = data.repository.remove(&alias).flatten();
+            let result = match (node, failed.remove(&alias)) {
+                (Some(node), None) => self.pull_from_node(owner, repo, node).await,
+                (_, Some(error)) if error.kind.as_deref() != Some("NOT_FOUND") => {
+                    Err(error.message.into())
+                }
+                _ => Err(error::Error::NotFound {
+                    url: self.graphql_url().to_string(),
+                }
+                .into()),
+            };
+            results.push((number, result));
+        }
+        Ok(results)
+    }
+
+    /// Turns a PR of a batch into its REST shape, fetching the rest of its
+    /// commits.
+    async fn pull_from_node(
+        &self,
+        owner: &str,
+        repo: &str,
+        node: PullNode,
+    ) -> Result<(PullRequest, Vec<Commit>), Box<dyn Error>> {
+        let number = node.number;
+        let (pull, mut connection) = node.into_parts();
+
+        // Past the connection's limit only the compare API has the whole
+        // list, so use REST for those.
+        if pull.commits > COMMITS_LIMIT {
+            let commits = self.pull_commits(owner, repo, &pull).await?;
+            return Ok((pull, commits));
+        }
+
+        let mut commits: Vec<Commit> = Vec::with_capacity(pull.commits as usize);
+        loop {
+            commits.extend(connection.nodes.into_iter().map(|node| node.commit.into()));
+            match connection.page_info.end_cursor {
+                Some(cursor) if connection.page_info.has_next_page => {
+                    connection = self.commits_page(owner, repo, number, &cursor).await?;
+                }
+                _ => break,
+            }
+        }
+        Ok((pull, commits))
+    }
+
+    async fn commits_page(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u32,
+        after: &str,
+    ) -> Result<CommitConnection, Box<dyn Error>> {
+        let query = format!(
+            "query($owner: String!, $repo: String!, $number: Int!, $after: String!) {{
+               repository(owner: $owner, name: $repo) {{
+                 pullRequest(number: $number) {{
+                   commits(first: 100, after: $after) {{ ...commitFields }}
+                 }}
+               }}
+             }}
+             {COMMIT_FIELDS}"
+        );
+        let data: PageData = self
+            .query(
+                &query,
+                json!({ "owner": owner, "repo": repo, "number": number, "after": after }),
+            )
+            .await?;
+        Ok(data.repository.pull_request.commits)
+    }
+
+    async fn query<T: serde::de::DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<T, Box<dyn Error>> {
+        let (data, errors) = self.query_partial(query, variables).await?;
+        if !errors.is_empty() {
+            let messages: Vec<String> = errors.into_iter().map(|error| error.message).collect();
+            return Err(messages.join("; ").into());
+        }
+        data.ok_or_else(|| "GraphQL response contained no data".into())
+    }
+
+    /// Like [`Self::query`], but fields that failed leave the rest of the
+    /// data, and come back as errors alongside it.
+    async fn query_partial<T: serde::de::DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<(Option<T>, Vec<ResponseError>), Box<dyn Error>> {
+        let response: Response<T> = self
+            .post_graphql(&json!({ "query": query, "variables": variables }))
+            .await?;
+        Ok((response.data, response.errors))
+    }
+}
+
+/// Sorts out the errors of a batch by PR. Errors about a single PR come
+/// with a path starting at its alias and only fail that PR; any other
+/// fails the whole batch.
+fn pr_errors(
+    errors: Vec<ResponseError>,
+    numbers: &[u32],
+) -> Result<HashMap<String, ResponseError>, String> {
+    let mut failed: HashMap<String, ResponseError> = HashMap::new();
+    let mut messages = Vec::new();
+    for error in errors {
+        match error.path.get(1).and_then(|alias| alias.as_str()) {
+            Some(alias) if alias_number(alias).is_some_and(|n| numbers.contains(&n)) => {
+                failed.entry(alias.to_string()).or_insert(error);
+            }
+            _ => messages.push(error.message),
+        }
+    }
+    if messages.is_empty() {
+        Ok(failed)
+    } else {
+        Err(messages.join("; "))
+    }
+}
+
+/// The number of the PR a batch asked for under `alias`, as in `pr12`.
+fn alias_number(alias: &str) -> Option<u32> {
+    alias.strip_prefix("pr")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(state: &str, mergeable: &str) -> PullNode {
+        serde_json::from_value(json!({
+            "number": 7,
+            "title": "Add a thing",
+            "url": "https://github.com/o/r/pull/7",
+            "author": { "login": "ada" },
+            "state": state,
+            "createdAt": "2024-01-01T00:00:00Z",
+            "mergedAt": (state == "MERGED").then_some("2024-01-02T00:00:00Z"),
+            "mergeCommit": (state == "MERGED").then(|| json!({ "oid": "abc" })),
+            "body": "",
+            "mergeable": mergeable,
+            "mergeStateStatus": "DIRTY",
+            "labels": { "nodes": [{ "name": "bug" }] },
+            "isDraft": false,
+            "baseRefName": "main",
+            "baseRefOid": "b0",
+            "headRefName": "feature",
+            "headRefOid": "h0",
+            "total": { "totalCount": 2 },
+            "commits": {
+                "pageInfo": { "hasNextPage": true, "endCursor": "c1" },
+                "nodes": [
+                    { "commit": {
+                        "oid": "1111", "url": "u1", "message": "First",
+                        "author": { "name": "Ada", "date": "2024-01-01T00:00:00Z" },
+                        "committer": null,
+                        "parents": { "totalCount": 2 }
+                    } }
+                ]
+            }
+        }))
+        .unwrap()
+    }
+
+    fn error(message: &str, path: serde_json::Value) -> ResponseError {
+        serde_json::from_value(json!({ "message": message, "type": "NOT_FOUND", "path": path }))
+            .unwrap()
+    }
+
+    #[test]
+    fn maps_a_merged_pr_to_its_rest_shape() {
+        let (pull, connection) = node("MERGED", "CONFLICTING").into_parts();
+        assert_eq!(pull.state, "closed");
+        assert!(pull.merged);
+        assert_eq!(pull.status(), "merged");
+        assert_eq!(pull.merge_commit_sha.as_deref(), Some("abc"));
+        assert_eq!(pull.mergeable, Some(false));
+        assert_eq!(pull.mergeable_state.as_deref(), Some("dirty"));
+        assert_eq!(pull.body, None);
+        assert_eq!(pull.author(), "ada");
+        assert_eq!(pull.commits, 2);
+        assert_eq!(
+            (pull.base.name.as_str(), pull.head.sha.as_str()),
+            ("main", "h0")
+        );
+        assert!(connection.page_info.has_next_page);
+    }
+
+    #[test]
+    fn maps_an_open_pr_of_unknown_mergeability() {
+        let (pull, _) = node("OPEN", "UNKNOWN").into_parts();
+        assert_eq!(pull.state, "open");
+        assert!(!pull.merged);
+        assert_eq!(pull.mergeable, None);
+        assert_eq!(pull.merge_commit_sha, None);
+    }
+
+    #[test]
+    fn maps_commits_with_missing_actors() {
+        let (_, connection) = node("OPEN", "MERGEABLE").into_parts();
+        let commit: Commit = connection.nodes.into_iter().next().unwrap().commit.into();
+        assert_eq!(commit.sha, "1111");
+        assert_eq!(commit.commit.author.name, "Ada");
+        assert_eq!(commit.commit.committer.name, "");
+        assert!(commit.is_merge());
+    }
+
+    #[test]
+    fn reads_the_number_off_an_alias() {
+        assert_eq!(alias_number("pr12"), Some(12));
+        assert_eq!(alias_number("pr"), None);
+        assert_eq!(alias_number("repository"), None);
+    }
+
+    #[test]
+    fn keeps_errors_about_a_requested_pr_to_it() {
+        let failed = pr_errors(
+            vec![
+                error("no PR 404", json!(["repository", "pr404"])),
+                error("again", json!(["repository", "pr404", "title"])),
+            ],
+            &[1, 404],
+        )
+        .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed["pr404"].message, "no PR 404");
+    }
+
+    #[test]
+    fn fails_the_batch_for_any_other_error() {
+        let errors = vec![
+            error("no PR 404", json!(["repository", "pr404"])),
+            error("no PR 5", json!(["repository", "pr5"])),
+            error("bad repository", json!(["repository"])),
+        ];
+        assert_eq!(
+            pr_errors(errors, &[404]).err().as_deref(),
+            Some("no PR 5; bad repository")
+        );
+    }
+}
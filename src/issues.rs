@@ -0,0 +1,75 @@
+use crate::Repository;
+use serde::Serialize;
+
+/// Words that make GitHub close the issue they precede once the PR merges.
+const KEYWORDS: [&str; 9] = [
+    "close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved",
+];
+
+/// An issue a PR closes.
+#[derive(Serialize, Debug, Clone)]
+pub struct LinkedIssue {
+    /// `owner/repo`, which may differ from the PR's.
+    pub repository: String,
+    pub number: u32,
+    pub title: String,
+    /// `open` or `closed`.
+    pub state: String,
+    pub url: String,
+}
+
+/// The issues a PR description says it closes, as in `Fixes #12`,
+/// `Closes owner/repo#12` or `Resolves https://github.com/owner/repo/issues/12`,
+/// each once and in the order they come.
+pub fn references(body: &str, repository: &Repository) -> Vec<(Repository, u32)> {
+    let mut references = Vec::new();
+    let words: Vec<&str> = body.split_whitespace().collect();
+    for pair in words.windows(2) {
+        let keyword = pair[0].trim_end_matches(':').to_lowercase();
+        if !KEYWORDS.contains(&keyword.as_str()) {
+            continue;
+        }
+        let target = pair[1].trim_end_matches(['.', ',', ';', ')']);
+        if let Some(reference) = parse_reference(target, repository) {
+            if !references.contains(&reference) {
+                references.push(reference);
+            }
+        }
+    }
+    references
+}
+
+fn parse_reference(text: &str, repository: &Repository) -> Option<(Repository, u32)> {
+    if let Some(path) = text
+        .strip_prefix("https://")
+        .and_then(|rest| rest.split_once('/'))
+        .map(|(_, path)| path)
+    {
+        let parts: Vec<&str> = path.split('/').collect();
+        return match parts.as_slice() {
+            [owner, name, "issues", number] => Some((
+                Repository::parse(&format!("{}/{}", owner, name))?,
+                number.parse().ok()?,
+            )),
+            _ => None,
+        };
+    }
+    let (prefix, number) = text.split_once('#')?;
+    let number = number.parse().ok()?;
+    let repository = match prefix {
+        "" => repository.clone(),
+        prefix => Repository::parse(prefix)?,
+    };
+    Some((repository, number))
+}
+
+impl LinkedIssue {
+    /// `#12`, or `owner/repo#12` when in another repository than the PR.
+    pub fn label(&self, repository: &Repository) -> String {
+        if self.repository == repository.to_string() {
+            format!("#{}", self.number)
+        } else {
+            format!("{}#{}", self.repository, self.number)
+        }
+    }
+}
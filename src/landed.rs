@@ -0,0 +1,56 @@
+use crate::github::GithubClient;
+use serde::Serialize;
+use std::error::Error;
+
+/// Whether the change a commit makes is on a branch.
+#[derive(Serialize, Debug, Clone)]
+pub struct Landing {
+    /// `yes` when the commit itself is on the branch, `squashed` when it
+    /// isn't but the PR's merge commit is, as after a squash or rebase
+    /// merge, and `no` otherwise.
+    pub state: &'static str,
+    /// The commit on the branch that carries the change, when it isn't the
+    /// commit itself.
+    pub via: Option<String>,
+}
+
+impl Landing {
+    pub const YES: Landing = Landing {
+        state: "yes",
+        via: None,
+    };
+    pub const NO: Landing = Landing {
+        state: "no",
+        via: None,
+    };
+
+    pub fn squashed(sha: &str) -> Landing {
+        Landing {
+            state: "squashed",
+            via: Some(sha.to_string()),
+        }
+    }
+
+    /// `✓`, `✓ as` the commit carrying the change, abbreviated to
+    /// `short_sha` characters, or `✗`.
+    pub fn describe(&self, short_sha: usize) -> String {
+        match (&self.via, self.state) {
+            (Some(via), _) => format!("✓ as {}", &via[..via.len().min(short_sha)]),
+            (None, "yes") => "✓".to_string(),
+            _ => "✗".to_string(),
+        }
+    }
+}
+
+/// Whether `sha` is on `branch`: comparing the branch to it finds
+/// nothing the branch lacks.
+pub async fn contains(
+    github: &GithubClient,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    sha: &str,
+) -> Result<bool, Box<dyn Error>> {
+    let status = github.compare_status(owner, repo, branch, sha).await?;
+    Ok(matches!(status.as_str(), "behind" | "identical"))
+}
@@ -1,145 +1,3128 @@
-use clap::Parser;
-use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT};
-use serde::Deserialize;
-use std::path::PathBuf;
+mod app;
+mod auth;
+mod backport;
+mod cache;
+mod check;
+mod cherry_pick;
+mod ci;
+mod color;
+mod comments;
+mod compare;
+mod config;
+mod dates;
+mod dedupe;
+mod device;
+mod diff;
+mod error;
+mod files;
+mod fixtures;
+mod github;
+mod graphql;
+mod issues;
+mod landed;
+mod notes;
+mod output;
+mod pager;
+mod patches;
+mod paths;
+mod pool;
+mod progress;
+mod prs;
+mod ratelimit;
+mod redact;
+mod remote;
+mod reviews;
+mod stack;
+mod table;
+mod timeline;
+mod trailers;
+mod update;
+
+use auth::AuthCommand;
+use cache::CacheCommand;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+use color::ColorChoice;
+use dates::{DateDisplay, Timezone};
+use dedupe::Dedupe;
+use futures::stream::{self, LocalBoxStream, StreamExt};
+use github::{Api, ClientOptions, Commit, Credentials, GithubClient, PullRequest};
+use output::{Column, Format, RenderOptions};
+use pager::Pager;
+use pool::TokenPool;
+use prs::ListFormat;
+use ratelimit::OnRateLimit;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use table::TableStyle;
+use tracing::{debug, info, warn};
+use tracing_subscriber::EnvFilter;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(after_help = DEFAULT_COMMAND_HELP)]
+struct Cli {
+    // Without a subcommand, `commits` is run; see `with_default_command`.
+    #[command(subcommand)]
+    command: Command,
+
+    /// Use the settings of this profile from ~/.config/pr-commits/config.toml
+    #[arg(long, global = true, env = "PR_COMMITS_PROFILE")]
+    profile: Option<String>,
+
+    /// Explain failures with the URL of the failed request and log progress to
+    /// stderr: -v for each PR, -vv for each request, -vvv for everything
+    /// [RUST_LOG overrides the level]
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+}
+
+/// The subcommand run when the arguments don't start with one.
+const DEFAULT_COMMAND: &str = "commits";
+
+const DEFAULT_COMMAND_HELP: &str = "Without a command, `commits` is run: \
+`pr-commits -p 12` is `pr-commits commits -p 12`.";
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the commits of PRs, one table per PR
+    #[command(after_help = error::EXIT_CODES_HELP)]
+    Commits(Args),
+    /// List PRs with their title, number of commits and URL
+    Prs(PrsArgs),
+    /// Write Markdown release notes with a line per PR
+    ReleaseNotes(ReleaseNotesArgs),
+    /// List the comments made on PRs
+    Comments(CommentsArgs),
+    /// List the files PRs change, with the lines added and removed
+    Files(FilesArgs),
+    /// Print the diff of PRs, or of one of their commits
+    Diff(DiffArgs),
+    /// List the PRs that contain a commit, such as the one a bisect found
+    Find(FindArgs),
+    /// List the commits of the PRs merged between two tags or other
+    /// revisions, like `commits` does
+    #[command(after_help = error::EXIT_CODES_HELP)]
+    Between(BetweenArgs),
+    /// Compare the commits of two PRs, by SHA and by patch ID, such as a
+    /// backport and its original
+    Compare(CompareArgs),
+    /// Draw how PRs stack on each other's branches, in merge order, with
+    /// each PR's own commits
+    Stack(StackArgs),
+    /// Cherry-pick the commits of PRs onto a new branch off another one, in
+    /// the git clone of the current directory, and open a PR of it
+    Backport(BackportArgs),
+    /// Store, remove or inspect the GitHub token
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommand,
+    },
+    /// Inspect or empty the on-disk response cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// Print a completion script for a shell, to be saved where the shell
+    /// looks for them
+    Completions { shell: clap_complete::Shell },
+    /// Print the man page, in roff
+    Man {
+        /// Instead, write a page for each command into DIR: pr-commits.1,
+        /// pr-commits-commits.1 and so on
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+    /// Replace this executable with the latest release, after checking its
+    /// SHA-256 checksum
+    SelfUpdate {
+        /// Only say whether a newer release is out
+        #[arg(long)]
+        check: bool,
+        /// Root of the REST API to look up the releases in
+        #[arg(long, value_name = "URL", default_value = github::API_URL)]
+        api_url: String,
+    },
+}
+
+impl Command {
+    /// The fetch options of the commands that fetch PRs.
+    fn fetch(&self) -> Option<&FetchArgs> {
+        match self {
+            Command::Commits(args) => Some(&args.fetch),
+            Command::Prs(args) => Some(&args.fetch),
+            Command::ReleaseNotes(args) => Some(&args.fetch),
+            Command::Comments(args) => Some(&args.fetch),
+            Command::Files(args) => Some(&args.fetch),
+            Command::Diff(args) => Some(&args.fetch),
+            Command::Find(args) => Some(&args.fetch),
+            Command::Between(args) => Some(&args.commits.fetch),
+            Command::Compare(args) => Some(&args.fetch),
+            Command::Stack(args) => Some(&args.fetch),
+            Command::Backport(_)
+            | Command::Auth { .. }
+            | Command::Cache { .. }
+            | Command::Completions { .. }
+            | Command::Man { .. }
+            | Command::SelfUpdate { .. } => None,
+        }
+    }
+}
+
+/// Puts [`DEFAULT_COMMAND`] in front of the arguments unless they name a
+/// command, so that `pr-commits -p 12` keeps working. Global options may
+/// come before the command; `--help` and `--version` alone are left to the
+/// top level.
+fn with_default_command(mut args: Vec<std::ffi::OsString>) -> Vec<std::ffi::OsString> {
+    use clap::CommandFactory;
+    let cli = Cli::command();
+    let mut index = 1;
+    while let Some(arg) = args.get(index).and_then(|arg| arg.to_str()) {
+        match arg {
+            "--profile" => index += 2,
+            _ if arg.starts_with("--profile=") || arg.starts_with("-v") || arg == "--verbose" => {
+                index += 1
+            }
+            _ => break,
+        }
+    }
+    let Some(first) = args.get(index) else {
+        return args;
+    };
+    let first = first.to_string_lossy();
+    let is_command = first == "help"
+        || cli.get_subcommands().any(|command| {
+            command.get_name() == first || command.get_all_aliases().any(|alias| alias == first)
+        });
+    let top_level = matches!(first.as_ref(), "-h" | "--help" | "-V" | "--version");
+    if !is_command && !top_level {
+        args.insert(index, DEFAULT_COMMAND.into());
+    }
+    args
+}
+
+// Options for listing the commits of PRs; a doc comment here would replace
+// the command's `about`.
+#[derive(clap::Args, Debug)]
 struct Args {
-    /// GitHub repository owner (e.g., "your_org")
-    #[arg(short, long)]
-    owner: String,
+    #[command(flatten)]
+    selection: SelectionArgs,
 
-    /// GitHub repository name (e.g., "your_repo")
-    #[arg(short, long)]
-    repo: String,
+    /// Output format [default: table]
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
 
-    /// Path to the file containing your GitHub token
-    #[arg(short, long)]
-    token_path: PathBuf,
+    /// Comma-separated list of columns to show in the tabular formats
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = Column::DEFAULT)]
+    columns: Vec<Column>,
 
-    /// List of pull request numbers to fetch
-    #[arg(short, long, required = true, num_args=1..)]
-    prs: Vec<u32>,
+    /// Border style for the table format
+    #[arg(long, value_enum, default_value_t = TableStyle::Ascii)]
+    table_style: TableStyle,
+
+    /// Truncate the table to this many columns instead of the terminal width
+    #[arg(long)]
+    max_width: Option<usize>,
+
+    /// Never truncate the table, even when it is wider than the terminal
+    #[arg(long, conflicts_with = "max_width")]
+    no_truncate: bool,
+
+    /// When to color the table output
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Don't emit terminal hyperlinks for SHAs and PR headers
+    #[arg(long)]
+    no_hyperlinks: bool,
+
+    /// Abbreviate SHAs in the tabular formats to N characters (default 8)
+    // Without N, `output::SHORT_SHA`.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(4..=40)
+    )]
+    short_sha: Option<Option<usize>>,
+
+    /// strftime-style format for commit dates in the tabular formats
+    #[arg(long, value_parser = dates::parse_date_format)]
+    date_format: Option<String>,
+
+    /// Convert commit dates to this timezone: local, utc, or a name like Europe/Berlin
+    #[arg(long, value_parser = dates::parse_timezone)]
+    timezone: Option<Timezone>,
+
+    /// Show commit dates as "3 days ago" in the human-readable formats
+    #[arg(long, conflicts_with_all = ["date_format", "timezone"])]
+    relative_dates: bool,
+
+    /// Show the full commit message, not just its first line
+    #[arg(long)]
+    full_message: bool,
+
+    /// Give every Co-authored-by trailer its own row, attributed to that co-author
+    #[arg(long)]
+    expand_coauthors: bool,
+
+    /// Show each PR's description under its heading, and add it to the
+    /// structured formats
+    #[arg(long)]
+    include_body: bool,
+
+    /// Fetch each PR's reviews and sum up who approved and who requested
+    /// changes after its commits; one more request per PR
+    #[arg(long)]
+    reviews: bool,
+
+    /// Fetch the check runs of each commit and add a CI status column: ✓, ✗
+    /// with the failing checks, or pending; one more request per commit
+    #[arg(long)]
+    checks: bool,
+
+    /// List the files each commit changes under it, with the lines added
+    /// and removed; one more request per commit
+    #[arg(long)]
+    show_files: bool,
+
+    /// Only show the commits that change a file matching PATH, which may be
+    /// repeated: `**` stands for any number of directories and `*` for
+    /// anything within one, and a directory takes in everything under it; one
+    /// more request per commit
+    #[arg(long = "paths", value_name = "PATH")]
+    paths: Vec<String>,
+
+    /// Add columns with the lines each commit adds and removes and the
+    /// number of files it changes, and sum them up for each PR; one more
+    /// request per commit
+    #[arg(long)]
+    diffstat: bool,
+
+    /// Save each commit as a patch into DIR, numbered in order across all
+    /// PRs like `git format-patch` does, for `git am DIR/*.patch`; one more
+    /// request per commit
+    #[arg(long, value_name = "DIR")]
+    download_patches: Option<PathBuf>,
+
+    /// Add a column saying whether each commit's change is on BRANCH: the
+    /// commit itself, or the PR's merge commit after a squash or rebase
+    /// merge; one more request per commit
+    #[arg(long, value_name = "BRANCH")]
+    verify_landed: Option<String>,
+
+    /// Show a commit several of the PRs have only under the first of them,
+    /// with a column listing the others; `patch-id` also takes commits
+    /// making the same change for the same, with one more request per
+    /// commit
+    #[arg(
+        long,
+        value_enum,
+        value_name = "BY",
+        num_args = 0..=1,
+        default_missing_value = "sha"
+    )]
+    dedupe: Option<Dedupe>,
+
+    /// Instead of listing the commits, write a shell script that checks out
+    /// BRANCH and cherry-picks the commits of each PR onto it, in order and
+    /// leaving out merge commits
+    #[arg(long, value_name = "BRANCH", conflicts_with_all = ["format", "template"])]
+    emit_cherry_pick_script: Option<String>,
+
+    /// Run the cherry-picks in the git clone of the current directory
+    /// instead of writing the script
+    #[arg(long, requires = "emit_cherry_pick_script")]
+    apply: bool,
+
+    /// Show the combined CI status of each PR's head commit under its
+    /// heading; two more requests per PR
+    #[arg(long)]
+    head_status: bool,
+
+    /// Exit with a dedicated status if CI is failing or still pending on the
+    /// head of any PR; implies --head-status
+    #[arg(long)]
+    require_green: bool,
+
+    /// Exit with a dedicated status if any open PR has merge conflicts,
+    /// asking GitHub again for PRs it hasn't checked yet
+    #[arg(long)]
+    fail_on_conflicts: bool,
+
+    /// List the issues each PR closes under its heading, by the "Fixes #12"
+    /// keywords of its description, or as GitHub links them with --api
+    /// graphql; one more request per issue with --api rest
+    #[arg(long)]
+    linked_issues: bool,
+
+    /// Fetch each PR's timeline and list force pushes, base changes, review
+    /// requests, reviews, closes and reopens after its commits, with a
+    /// warning if it was force-pushed after approval; one more request per PR
+    #[arg(long)]
+    timeline: bool,
+
+    /// Exit with a dedicated status if any PR has no commits
+    #[arg(long)]
+    fail_on_empty: bool,
+
+    /// Render the output with a Tera template file instead of a built-in format
+    #[arg(long, conflicts_with = "format")]
+    template: Option<PathBuf>,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+// Options for `prs`.
+#[derive(clap::Args, Debug)]
+struct PrsArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = ListFormat::Table)]
+    format: ListFormat,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+// Options for `release-notes`.
+#[derive(clap::Args, Debug)]
+struct ReleaseNotesArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// List the subject line of each commit under its PR
+    #[arg(long)]
+    commits: bool,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+// Options for `files`.
+#[derive(clap::Args, Debug)]
+struct FilesArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = ListFormat::Table)]
+    format: ListFormat,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
 }
 
-#[derive(Deserialize, Debug)]
-struct Commit {
+// Options for `diff`.
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Print the diff of this commit of the PR instead of the PR's; it may
+    /// be abbreviated to as few as 4 characters
+    #[arg(long, value_name = "SHA", value_parser = parse_sha_prefix)]
+    sha: Option<String>,
+
+    /// When to color the diff
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+// Options for `find`; the PRs come from --sha rather than --prs.
+#[derive(clap::Args, Debug)]
+#[command(mut_arg("prs", |arg| arg.required_unless_present("sha")))]
+struct FindArgs {
+    /// The commit to find the PRs of; GitHub also takes in the PRs whose
+    /// merge commit it is
+    #[arg(long, value_name = "SHA")]
     sha: String,
-    commit: CommitInfo,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = ListFormat::Table)]
+    format: ListFormat,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+// Options for `between`: those of `commits`, with the PRs coming from the
+// range rather than --prs.
+#[derive(clap::Args, Debug)]
+#[command(mut_arg("prs", |arg| arg.required_unless_present("base")))]
+struct BetweenArgs {
+    /// The tag, branch or commit the range starts after, such as the
+    /// previous release
+    base: String,
+
+    /// The tag, branch or commit the range ends at
+    head: String,
+
+    #[command(flatten)]
+    commits: Args,
+}
+
+// Options for `compare`.
+#[derive(clap::Args, Debug)]
+struct CompareArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = ListFormat::Table)]
+    format: ListFormat,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+// Options for `stack`.
+#[derive(clap::Args, Debug)]
+struct StackArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Output format; CSV has a row per PR, without its commits
+    #[arg(short, long, value_enum, default_value_t = ListFormat::Table)]
+    format: ListFormat,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+// Options for `backport`.
+#[derive(clap::Args, Debug)]
+struct BackportArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Branch to backport the PRs onto, such as release/1.2
+    #[arg(long, value_name = "BRANCH")]
+    onto: String,
+
+    /// Name of the branch to create for the backport
+    /// [default: backport-<PRs>-to-<BRANCH>]
+    #[arg(long, value_name = "NAME")]
+    branch: Option<String>,
+
+    /// Remote to fetch --onto from and push the branch to
+    #[arg(long, value_name = "NAME", default_value = "origin")]
+    remote: String,
+
+    /// Push the branch and open a PR of it against --onto
+    #[arg(long)]
+    open_pr: bool,
+
+    /// Print the git commands instead of running them
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+// Options for `comments`.
+#[derive(clap::Args, Debug)]
+#[command(group(clap::ArgGroup::new("kinds").required(true).multiple(true)))]
+struct CommentsArgs {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// List the comments on lines of the diff: file, line, author and text
+    #[arg(long, group = "kinds")]
+    review: bool,
+
+    /// List the comments on the conversation: author, time and text
+    #[arg(long, group = "kinds")]
+    conversation: bool,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = ListFormat::Table)]
+    format: ListFormat,
+
+    #[command(flatten)]
+    fetch: FetchArgs,
+
+    #[command(flatten)]
+    connection: ConnectionArgs,
+}
+
+// Which PRs to report on, shared by the commands that fetch PRs.
+#[derive(clap::Args, Debug)]
+struct SelectionArgs {
+    /// GitHub repository as OWNER/REPO, instead of --owner and --repo
+    #[arg(value_name = "OWNER/REPO")]
+    repository: Option<String>,
+
+    /// GitHub repository owner (e.g., "your_org") [default: from $GITHUB_REPOSITORY,
+    /// the origin remote of the git clone, or the config file]
+    #[arg(short, long, env = "PR_COMMITS_OWNER")]
+    owner: Option<String>,
+
+    /// GitHub repository name (e.g., "your_repo"), or OWNER/REPO; repeat to
+    /// fetch the same PRs from several repositories
+    /// [default: from $GITHUB_REPOSITORY,
+    /// the origin remote of the git clone, or the config file]
+    #[arg(short, long = "repo", value_name = "REPO", env = "PR_COMMITS_REPO")]
+    repos: Vec<String>,
+
+    /// Read repositories from this file, or stdin for "-", one per line as
+    /// OWNER/REPO followed by that repository's PRs; a repository listed
+    /// without PRs gets those of --prs and the discovery options
+    #[arg(long, value_name = "PATH")]
+    repos_file: Option<PathBuf>,
+
+    /// Fetch the PRs from every repository of this organization, narrowed
+    /// down with --topic and --repo-pattern
+    #[arg(long, value_name = "ORG", conflicts_with_all = ["repository", "repos", "repos_file"])]
+    org: Option<String>,
+
+    /// With --org, only include repositories with this topic; repeat to
+    /// require several
+    #[arg(long = "topic", value_name = "TOPIC")]
+    topics: Vec<String>,
+
+    /// With --org, only include repositories whose name matches this
+    /// pattern, where * matches any run of characters (e.g., "service-*")
+    #[arg(long, value_name = "PATTERN")]
+    repo_pattern: Option<String>,
+
+    /// List of pull requests to fetch: numbers, ranges (120..135, or 120.. up to
+    /// the newest PR), OWNER/REPO#N, or URLs like https://github.com/OWNER/REPO/pull/N;
+    /// with --keep-going, numbers in ranges that aren't PRs are skipped
+    #[arg(
+        short,
+        long,
+        required_unless_present_any = ["prs_file", "repos_file", "all_open", "merged_since", "merged_until", "milestone", "search"],
+        num_args = 1..,
+        value_parser = parse_pr
+    )]
+    prs: Vec<PrArg>,
+
+    /// Read more PRs from this file, or stdin for "-", one per line in any
+    /// form --prs takes
+    #[arg(long, value_name = "PATH")]
+    prs_file: Option<PathBuf>,
+
+    /// Fetch every open PR of the repository
+    #[arg(long)]
+    all_open: bool,
+
+    /// Fetch the PRs merged on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = dates::parse_day)]
+    merged_since: Option<NaiveDate>,
+
+    /// Fetch the PRs merged on or before this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = dates::parse_day)]
+    merged_until: Option<NaiveDate>,
+
+    /// Fetch every PR attached to the milestone with this title
+    #[arg(long, value_name = "TITLE")]
+    milestone: Option<String>,
+
+    /// Fetch the PRs matching a GitHub search query, such as
+    /// 'is:merged base:release/1.x label:hotfix'; limited to the repository
+    /// unless the query has a repo: qualifier
+    #[arg(long, value_name = "QUERY")]
+    search: Option<String>,
+
+    /// With --all-open, --merged-since/--merged-until, --milestone or
+    /// --search, only include PRs carrying this label; repeat to require
+    /// several
+    #[arg(long = "label", value_name = "LABEL")]
+    labels: Vec<String>,
+
+    /// With --all-open, --merged-since/--merged-until, --milestone or
+    /// --search, only include PRs opened by this user; repeat to allow
+    /// several
+    #[arg(long = "pr-author", value_name = "LOGIN")]
+    pr_authors: Vec<String>,
+
+    /// Skip fetched PRs that don't carry this label, even ones asked for by
+    /// number; repeat to require several
+    #[arg(long = "filter-label", value_name = "LABEL")]
+    filter_labels: Vec<String>,
+
+    /// Set by `find --sha`: fetch the PRs that contain this commit.
+    #[arg(skip)]
+    containing: Option<String>,
+
+    /// Set by `between`: fetch the PRs merged between these revisions.
+    #[arg(skip)]
+    between: Option<(String, String)>,
+}
+
+// How PRs are fetched, shared by the commands that fetch PRs.
+#[derive(clap::Args, Debug)]
+struct FetchArgs {
+    /// Print only the rows, without headings, column headers or borders;
+    /// twice, print nothing and just set the exit status
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "verbose")]
+    quiet: u8,
+
+    /// Write straight to stdout instead of piping through $PAGER
+    #[arg(long)]
+    no_pager: bool,
+
+    /// API used to fetch PRs and their commits
+    #[arg(long, value_enum, default_value_t = Api::Rest)]
+    api: Api,
+
+    /// Report PRs that fail to fetch at the end instead of stopping at the first
+    #[arg(long)]
+    keep_going: bool,
+
+    /// Maximum number of PRs (or GraphQL batches) fetched at the same time
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    concurrency: u32,
+
+    /// Print the API requests that would be made, without making them
+    #[arg(long)]
+    dry_run: bool,
 }
 
-#[derive(Deserialize, Debug)]
-struct PullRequest {
-    title: String,
+/// How to reach and authenticate with GitHub, shared by every command that
+/// talks to the API.
+#[derive(clap::Args, Debug)]
+struct ConnectionArgs {
+    /// Path to a file containing your GitHub token, or several tokens one per
+    /// line; repeat to pool tokens from several files [default: $GITHUB_TOKEN,
+    /// $GH_TOKEN, the OS keyring or the gh CLI's login]
+    #[arg(short, long)]
+    token_path: Vec<PathBuf>,
+
+    /// Authenticate as this GitHub App instead of with a token
+    #[arg(
+        long,
+        value_name = "ID",
+        requires = "app_key",
+        conflicts_with = "token_path"
+    )]
+    app_id: Option<u64>,
+
+    /// Private key (PEM) of the GitHub App given with --app-id
+    #[arg(long, value_name = "PATH", requires = "app_id")]
+    app_key: Option<PathBuf>,
+
+    /// Installation of the GitHub App to act as [default: the one on the repository]
+    #[arg(long, value_name = "ID", requires = "app_id")]
+    installation_id: Option<u64>,
+
+    /// Root of the REST API, e.g. https://github.example.com/api/v3 for GitHub Enterprise Server
+    /// [default: https://api.github.com]
+    #[arg(long, value_name = "URL")]
+    api_url: Option<String>,
+
+    /// GraphQL endpoint, if not the one that goes with --api-url
+    #[arg(long, value_name = "URL")]
+    graphql_url: Option<String>,
+
+    /// REST API version to request with the X-GitHub-Api-Version header
+    #[arg(long, value_name = "VERSION", default_value = github::API_VERSION)]
+    api_version: String,
+
+    /// What to do when the API rate limit is exhausted
+    #[arg(long, value_enum, default_value_t = OnRateLimit::Fail)]
+    on_rate_limit: OnRateLimit,
+
+    /// How many times to retry a request after a 5xx response or network error
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// Delay in milliseconds before the first retry, doubled for each further one
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    retry_backoff: u64,
+
+    /// Don't reuse or store responses in the on-disk ETag cache
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Serve everything from the on-disk cache without contacting GitHub
+    #[arg(long, conflicts_with = "no_cache")]
+    offline: bool,
+
+    /// Seconds to wait for a connection to GitHub before giving up
+    #[arg(long, value_name = "SECS", default_value_t = 10)]
+    connect_timeout: u64,
+
+    /// Seconds a single request may take before it is abandoned
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    request_timeout: u64,
+
+    /// Send every request through this HTTP(S) proxy instead of the one in
+    /// HTTPS_PROXY/HTTP_PROXY; hosts in NO_PROXY still bypass it
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// PEM file with additional root certificates to trust, for TLS-inspecting proxies
+    #[arg(long, value_name = "PATH", env = "PR_COMMITS_CA_CERT")]
+    ca_cert: Option<PathBuf>,
+
+    /// Save every API response to this directory, to replay later with --replay
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["replay", "offline"])]
+    record: Option<PathBuf>,
+
+    /// Answer every API request from responses saved with --record; needs no token
+    #[arg(long, value_name = "DIR", conflicts_with = "offline")]
+    replay: Option<PathBuf>,
 }
 
-#[derive(Deserialize, Debug)]
-struct CommitInfo {
-    author: UserInfo,
-    message: String,
+impl ConnectionArgs {
+    fn api_url(&self) -> &str {
+        self.api_url.as_deref().unwrap_or(github::API_URL)
+    }
+
+    /// Fills in what the command line left out from the config file.
+    fn apply(&mut self, profile: &config::Profile) {
+        if self.token_path.is_empty() && self.app_id.is_none() {
+            self.token_path.extend(profile.token_path.clone());
+        }
+        if self.api_url.is_none() {
+            self.api_url = profile.api_url.clone();
+        }
+    }
+
+    /// A client for the API. A GitHub App installation is looked up on the
+    /// repository, or on the organization `owner` without one.
+    fn client(
+        &self,
+        owner: &str,
+        repo: Option<&str>,
+    ) -> Result<GithubClient, Box<dyn std::error::Error>> {
+        let credentials = match (self.app_id, &self.app_key) {
+            _ if self.replay.is_some() => Credentials::None,
+            (Some(app_id), Some(app_key)) => Credentials::App(app::AppAuth::new(
+                app_id,
+                app_key,
+                self.installation_id,
+                owner,
+                repo,
+            )?),
+            _ => Credentials::Tokens(TokenPool::new(auth::tokens(
+                &self.token_path,
+                &auth::host(self.api_url()),
+            )?)),
+        };
+        GithubClient::new(
+            credentials,
+            ClientOptions {
+                api_url: self.api_url().to_string(),
+                graphql_url: self.graphql_url.clone(),
+                api_version: self.api_version.clone(),
+                on_rate_limit: self.on_rate_limit,
+                retries: self.retries,
+                retry_backoff: Duration::from_millis(self.retry_backoff),
+                // Recordings need whole responses, not 304s to revalidate.
+                cache: !self.no_cache && self.record.is_none() && self.replay.is_none(),
+                offline: self.offline,
+                connect_timeout: Duration::from_secs(self.connect_timeout),
+                request_timeout: Duration::from_secs(self.request_timeout),
+                proxy: self.proxy.clone(),
+                ca_cert: self.ca_cert.clone(),
+                record: self.record.clone(),
+                replay: self.replay.clone(),
+            },
+        )
+    }
 }
 
-#[derive(Deserialize, Debug)]
-struct UserInfo {
+/// A GitHub repository, as `owner/name`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Repository {
+    owner: String,
     name: String,
-    date: String,
 }
 
-async fn fetch_pr_title(
-    owner: &str,
-    repo: &str,
-    pr_number: u32,
-    token: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/pulls/{}",
-        owner, repo, pr_number
-    );
+impl Repository {
+    /// Parses `owner/name`.
+    fn parse(text: &str) -> Option<Repository> {
+        let (owner, name) = text.trim().split_once('/')?;
+        if owner.is_empty() || name.is_empty() || name.contains('/') {
+            return None;
+        }
+        Some(Repository {
+            owner: owner.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// The repository of a GitHub Actions run, or else of the git clone in
+    /// the current directory.
+    fn detect() -> Option<Repository> {
+        std::env::var("GITHUB_REPOSITORY")
+            .ok()
+            .and_then(|repository| Repository::parse(&repository))
+            .or_else(|| remote::origin().and_then(|origin| Repository::parse(&origin)))
+    }
+}
+
+impl std::fmt::Display for Repository {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.owner, self.name)
+    }
+}
+
+impl Args {
+    /// Fills in what the command line left out from the config file.
+    fn apply(&mut self, profile: &config::Profile) -> Result<(), Box<dyn std::error::Error>> {
+        if let (None, Some(format)) = (self.format, &profile.format) {
+            let parsed = Format::from_str(format, true)
+                .map_err(|_| format!("invalid format {:?} in the config file", format))?;
+            self.format = Some(parsed);
+        }
+        self.connection.apply(profile);
+        Ok(())
+    }
+}
+
+impl SelectionArgs {
+    /// The repositories PRs given by number are in: the `OWNER/REPO`
+    /// argument and every `--repo`, or else the single one found by
+    /// [`SelectionArgs::repository`].
+    fn repositories(
+        &self,
+        profile: &config::Profile,
+    ) -> Result<Vec<Repository>, Box<dyn std::error::Error>> {
+        if self.repos.is_empty() {
+            return Ok(vec![self.repository(None, profile)?]);
+        }
+        let mut repositories = Vec::new();
+        if let Some(combined) = &self.repository {
+            repositories.push(parse_repository(combined)?);
+        }
+        for repo in &self.repos {
+            let repository = self.repository(Some(repo), profile)?;
+            if !repositories.contains(&repository) {
+                repositories.push(repository);
+            }
+        }
+        Ok(repositories)
+    }
+
+    /// A repository given as `OWNER/REPO`, or from `--owner` and `repo` (or
+    /// their variables), `GITHUB_REPOSITORY` as GitHub Actions sets it, the
+    /// git clone in the current directory, or else the config file.
+    fn repository(
+        &self,
+        repo: Option<&str>,
+        profile: &config::Profile,
+    ) -> Result<Repository, Box<dyn std::error::Error>> {
+        let combined = self
+            .repository
+            .as_deref()
+            .filter(|_| repo.is_none())
+            .or(repo.filter(|repo| repo.contains('/')));
+        if let Some(combined) = combined {
+            return parse_repository(combined);
+        }
+        // Only look around when the command line leaves something out.
+        let detected = match (&self.owner, repo) {
+            (Some(_), Some(_)) => None,
+            _ => Repository::detect(),
+        };
+        let owner = self
+            .owner
+            .clone()
+            .or_else(|| detected.as_ref().map(|detected| detected.owner.clone()))
+            .or_else(|| profile.owner.clone());
+        let name = repo
+            .map(str::to_string)
+            .or_else(|| detected.as_ref().map(|detected| detected.name.clone()))
+            .or_else(|| profile.repo.clone());
+        match (owner, name) {
+            (Some(owner), Some(name)) => Ok(Repository { owner, name }),
+            _ => Err(
+                "no repository given: pass --owner and --repo, run inside a clone of \
+                 the repository, or set owner and repo in the config file"
+                    .into(),
+            ),
+        }
+    }
+}
+
+fn parse_repository(text: &str) -> Result<Repository, Box<dyn std::error::Error>> {
+    Repository::parse(text)
+        .ok_or_else(|| format!("invalid repository {:?}: expected OWNER/REPO", text).into())
+}
+
+/// PRs as given on the command line, which may name their repository.
+#[derive(Clone, Debug)]
+struct PrArg {
+    repository: Option<Repository>,
+    selection: Selection,
+}
+
+/// Which PRs of a repository to fetch.
+#[derive(Clone, Debug)]
+enum Selection {
+    One(u32),
+    /// `A..B`, both ends included.
+    Range(u32, u32),
+    /// `A..`, up to the newest PR in the repository.
+    From(u32),
+    /// PRs looked up through the API, narrowed down by [`Filters`].
+    Discover(Discovery),
+}
+
+/// The ways PRs are looked up rather than given by number.
+#[derive(Clone, Debug)]
+enum Discovery {
+    /// Every open PR, for `--all-open`.
+    Open,
+    /// The PRs merged within a window of days, both ends included.
+    Merged {
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    },
+    /// The PRs attached to the milestone with this title.
+    Milestone(String),
+    /// The PRs matching a search query, for `--search`.
+    Search(String),
+    /// The PRs that contain a commit, for `find --sha`.
+    Containing(String),
+    /// The merged PRs that brought in the commits `head` has and `base`
+    /// lacks, for `between`.
+    Between(String, String),
+}
+
+impl Discovery {
+    /// The search query that finds the PRs in `repository`, for the ways
+    /// that go through the search API.
+    fn query(&self, repository: &Repository, filters: &Filters) -> Option<String> {
+        let query = match self {
+            Discovery::Merged { since, until } => {
+                let merged = match (since, until) {
+                    (Some(since), Some(until)) => format!("{}..{}", since, until),
+                    (Some(since), None) => format!(">={}", since),
+                    (None, Some(until)) => format!("<={}", until),
+                    (None, None) => "*".to_string(),
+                };
+                format!("repo:{} is:pr is:merged merged:{}", repository, merged)
+            }
+            Discovery::Search(query) => {
+                let mut query = query.clone();
+                if search_repository(&query).is_none() {
+                    query = format!("{} repo:{}", query, repository);
+                }
+                if !query
+                    .split_whitespace()
+                    .any(|word| word == "is:pr" || word == "type:pr")
+                {
+                    query.push_str(" is:pr");
+                }
+                query
+            }
+            Discovery::Open
+            | Discovery::Milestone(_)
+            | Discovery::Containing(_)
+            | Discovery::Between(..) => return None,
+        };
+        Some(query + &filters.qualifiers())
+    }
+}
+
+/// The repository named by the first `repo:` qualifier of a search query.
+fn search_repository(query: &str) -> Option<Repository> {
+    query
+        .split_whitespace()
+        .find_map(|word| word.strip_prefix("repo:"))
+        .and_then(Repository::parse)
+}
+
+impl Selection {
+    fn parse(text: &str) -> Result<Selection, String> {
+        let invalid = || format!("invalid PR number or range {:?}", text);
+        let Some((first, last)) = text.split_once("..") else {
+            return text.parse().map(Selection::One).map_err(|_| invalid());
+        };
+        let first = first.parse().map_err(|_| invalid())?;
+        if last.is_empty() {
+            return Ok(Selection::From(first));
+        }
+        let last = last.parse().map_err(|_| invalid())?;
+        if last < first {
+            return Err(format!("empty PR range {:?}", text));
+        }
+        Ok(Selection::Range(first, last))
+    }
+}
+
+/// Shortest SHA prefix taken, as git does for abbreviations.
+const MIN_SHA_PREFIX: usize = 4;
+
+/// Parses a commit SHA, possibly abbreviated, into lowercase.
+fn parse_sha_prefix(text: &str) -> Result<String, String> {
+    if !text.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("{:?} is not a commit SHA", text));
+    }
+    if !(MIN_SHA_PREFIX..=40).contains(&text.len()) {
+        return Err(format!(
+            "a commit SHA takes {} to 40 hex digits, not {}",
+            MIN_SHA_PREFIX,
+            text.len()
+        ));
+    }
+    Ok(text.to_ascii_lowercase())
+}
+
+/// Parses a PR number or range, a reference like `OWNER/REPO#N`, or the
+/// URL of a PR page such as `https://github.com/OWNER/REPO/pull/N/files`.
+fn parse_pr(text: &str) -> Result<PrArg, String> {
+    if text.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(PrArg {
+            repository: None,
+            selection: Selection::parse(text)?,
+        });
+    }
+    let invalid = || "expected a PR number, OWNER/REPO#N or the URL of a PR".to_string();
+    if let Some((repository, numbers)) = text.split_once('#') {
+        return Ok(PrArg {
+            repository: Some(Repository::parse(repository).ok_or_else(invalid)?),
+            selection: Selection::parse(numbers)?,
+        });
+    }
+    let url = reqwest::Url::parse(text).map_err(|_| invalid())?;
+    let segments: Vec<&str> = url.path_segments().ok_or_else(invalid)?.collect();
+    match segments.as_slice() {
+        [owner, name, "pull" | "pulls", number, ..] if !owner.is_empty() && !name.is_empty() => {
+            Ok(PrArg {
+                repository: Some(Repository {
+                    owner: owner.to_string(),
+                    name: name.to_string(),
+                }),
+                selection: Selection::One(number.parse().map_err(|_| invalid())?),
+            })
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Reads a list from a file, or from stdin for `-`, and parses each line
+/// with `parse`. Blank lines and lines starting with `#` are skipped.
+fn read_list<T>(
+    path: &Path,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+    let (name, contents) = if path == Path::new("-") {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents)?;
+        ("stdin".to_string(), contents)
+    } else {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("can't read {}: {}", path.display(), err))?;
+        (path.display().to_string(), contents)
+    };
+    contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            parse(line).map_err(|err| format!("line {} of {}: {}", index + 1, name, err).into())
+        })
+        .collect()
+}
+
+/// Reads the PRs listed in a file, one per line.
+fn read_prs(path: &Path) -> Result<Vec<PrArg>, Box<dyn std::error::Error>> {
+    read_list(path, parse_pr)
+}
+
+/// A line of `--repos-file`.
+struct RepoLine {
+    repository: Repository,
+    prs: Vec<PrArg>,
+}
+
+/// Reads the repositories listed in a file, each with the PRs that follow
+/// it on its line.
+fn read_repos(path: &Path) -> Result<Vec<RepoLine>, Box<dyn std::error::Error>> {
+    read_list(path, |line| {
+        let mut words = line.split_whitespace();
+        let first = words.next().unwrap_or_default();
+        let repository = Repository::parse(first)
+            .ok_or_else(|| format!("invalid repository {:?}: expected OWNER/REPO", first))?;
+        let prs = words.map(parse_pr).collect::<Result<_, _>>()?;
+        Ok(RepoLine { repository, prs })
+    })
+}
+
+/// PRs asked for, in the repository they were resolved to.
+struct Requested {
+    repository: Repository,
+    selection: Selection,
+}
+
+/// A PR to fetch.
+struct Target {
+    repository: Repository,
+    number: u32,
+    /// Whether the PR comes from a range, which may take in numbers that
+    /// are issues rather than PRs.
+    in_range: bool,
+}
+
+impl SelectionArgs {
+    /// The PRs asked for, grouped by repository. Those that don't name
+    /// their repository are looked for in each of the `--org` repositories
+    /// when `org` lists them, or else [`SelectionArgs::repositories`] and the bare
+    /// lines of `--repos-file`, which are only looked up if there are any
+    /// such PRs.
+    fn requested(
+        &self,
+        profile: &config::Profile,
+        org: Option<&[Repository]>,
+    ) -> Result<Vec<Requested>, Box<dyn std::error::Error>> {
+        // clap's `requires` doesn't hold inside the optional `Args`.
+        if self.org.is_none() && (!self.topics.is_empty() || self.repo_pattern.is_some()) {
+            return Err("--topic and --repo-pattern only apply with --org".into());
+        }
+        let mut prs = self.prs.clone();
+        if let Some(path) = &self.prs_file {
+            let listed = read_prs(path)?;
+            if listed.is_empty() {
+                return Err(format!("no PRs in {}", path.display()).into());
+            }
+            prs.extend(listed);
+        }
+        if self.all_open {
+            prs.push(PrArg {
+                repository: None,
+                selection: Selection::Discover(Discovery::Open),
+            });
+        }
+        if self.merged_since.is_some() || self.merged_until.is_some() {
+            prs.push(PrArg {
+                repository: None,
+                selection: Selection::Discover(Discovery::Merged {
+                    since: self.merged_since,
+                    until: self.merged_until,
+                }),
+            });
+        }
+        if let Some(title) = &self.milestone {
+            prs.push(PrArg {
+                repository: None,
+                selection: Selection::Discover(Discovery::Milestone(title.clone())),
+            });
+        }
+        if let Some(query) = &self.search {
+            prs.push(PrArg {
+                repository: search_repository(query),
+                selection: Selection::Discover(Discovery::Search(query.clone())),
+            });
+        }
+        if let Some(sha) = &self.containing {
+            prs.push(PrArg {
+                repository: None,
+                selection: Selection::Discover(Discovery::Containing(sha.clone())),
+            });
+        }
+        if let Some((base, head)) = &self.between {
+            prs.push(PrArg {
+                repository: None,
+                selection: Selection::Discover(Discovery::Between(base.clone(), head.clone())),
+            });
+        }
+        let mut requested = Vec::new();
+        let mut bare = Vec::new();
+        if let Some(path) = &self.repos_file {
+            let listed = read_repos(path)?;
+            if listed.is_empty() {
+                return Err(format!("no repositories in {}", path.display()).into());
+            }
+            for RepoLine { repository, prs } in listed {
+                if prs.is_empty() {
+                    bare.push(repository);
+                    continue;
+                }
+                requested.extend(prs.into_iter().map(|pr| Requested {
+                    repository: pr.repository.unwrap_or_else(|| repository.clone()),
+                    selection: pr.selection,
+                }));
+            }
+        }
+        let (named, shared): (Vec<PrArg>, Vec<PrArg>) =
+            prs.into_iter().partition(|pr| pr.repository.is_some());
+        requested.extend(named.into_iter().map(|pr| Requested {
+            repository: pr.repository.expect("partitioned on the repository"),
+            selection: pr.selection,
+        }));
+        if shared.is_empty() {
+            if let Some(repository) = bare.first() {
+                return Err(format!(
+                    "no PRs for {}: list them after it in the repos file, or pass --prs",
+                    repository
+                )
+                .into());
+            }
+        } else {
+            // The file's bare lines stand in for the local clone, but not
+            // for repositories given on the command line.
+            let mut repositories = bare;
+            if let Some(org) = org {
+                repositories.extend_from_slice(org);
+            } else if repositories.is_empty() || self.repository.is_some() || !self.repos.is_empty()
+            {
+                for repository in self.repositories(profile)? {
+                    if !repositories.contains(&repository) {
+                        repositories.push(repository);
+                    }
+                }
+            }
+            for repository in repositories {
+                requested.extend(shared.iter().map(|pr| Requested {
+                    repository: repository.clone(),
+                    selection: pr.selection.clone(),
+                }));
+            }
+        }
+        let mut order: Vec<Repository> = Vec::new();
+        for Requested { repository, .. } in &requested {
+            if !order.contains(repository) {
+                order.push(repository.clone());
+            }
+        }
+        requested.sort_by_key(|pr| {
+            order
+                .iter()
+                .position(|repository| *repository == pr.repository)
+        });
+        Ok(requested)
+    }
+}
+
+/// Expands PR ranges into the PRs to fetch, looking up the newest PR of
+/// the repository for open-ended ones, and finds the PRs that
+/// `--all-open`, `--merged-since`, `--milestone` and `--search` ask for.
+async fn expand(
+    github: &GithubClient,
+    requested: Vec<Requested>,
+    filters: &Filters,
+) -> Result<Vec<Target>, Box<dyn std::error::Error>> {
+    let mut targets = Vec::new();
+    for Requested {
+        repository,
+        selection,
+    } in requested
+    {
+        let (first, last) = match selection {
+            Selection::One(number) => {
+                targets.push(Target {
+                    repository,
+                    number,
+                    in_range: false,
+                });
+                continue;
+            }
+            Selection::Range(first, last) => (first, last),
+            Selection::From(first) => {
+                let newest = github
+                    .newest_pull_number(&repository.owner, &repository.name)
+                    .await?;
+                (first, newest.unwrap_or(0))
+            }
+            Selection::Discover(discovery) => {
+                let found = discover(github, &repository, &discovery, filters).await?;
+                targets.extend(found.into_iter().map(|(repository, number)| Target {
+                    repository,
+                    number,
+                    in_range: false,
+                }));
+                continue;
+            }
+        };
+        targets.extend((first..=last).map(|number| Target {
+            repository: repository.clone(),
+            number,
+            in_range: true,
+        }));
+    }
+    Ok(targets)
+}
+
+/// The PRs `discovery` finds that pass `filters`, sorted by repository and
+/// number. Searches take the filters as qualifiers; lists are filtered
+/// here.
+async fn discover(
+    github: &GithubClient,
+    repository: &Repository,
+    discovery: &Discovery,
+    filters: &Filters,
+) -> Result<Vec<(Repository, u32)>, Box<dyn std::error::Error>> {
+    if let Some(query) = discovery.query(repository, filters) {
+        let mut found: Vec<(Repository, u32)> = github
+            .search(&query)
+            .await?
+            .iter()
+            .filter(|hit| hit.is_pull_request())
+            .filter_map(|hit| {
+                let (owner, name) = hit.repository()?;
+                let repository = Repository {
+                    owner: owner.to_string(),
+                    name: name.to_string(),
+                };
+                Some((repository, hit.number))
+            })
+            .collect();
+        found.sort_unstable();
+        return Ok(found);
+    }
+    let Repository { owner, name } = repository;
+    let pulls = match discovery {
+        Discovery::Merged { .. } | Discovery::Search(_) => unreachable!("searched above"),
+        Discovery::Open => github.open_pulls(owner, name).await?,
+        Discovery::Milestone(title) => {
+            let milestone = github
+                .milestone_number(owner, name, title)
+                .await?
+                .ok_or_else(|| format!("no milestone {:?} in {}", title, repository))?;
+            github.milestone_pulls(owner, name, milestone).await?
+        }
+        Discovery::Containing(sha) => github.commit_pulls(owner, name, sha).await?,
+        Discovery::Between(base, head) => {
+            // A commit can also be in PRs still open, or merged into
+            // another branch after the range took it in another way.
+            let mut pulls: Vec<github::PullSummary> = Vec::new();
+            for commit in github.compare_commits(owner, name, base, head).await? {
+                for pull in github.commit_pulls(owner, name, &commit.sha).await? {
+                    if pull.merged_at.is_some()
+                        && !pulls.iter().any(|known| known.number == pull.number)
+                    {
+                        pulls.push(pull);
+                    }
+                }
+            }
+            pulls
+        }
+    };
+    let mut numbers: Vec<u32> = pulls
+        .into_iter()
+        .filter(|pull| filters.matches(pull))
+        .map(|pull| pull.number)
+        .collect();
+    numbers.sort_unstable();
+    Ok(numbers
+        .into_iter()
+        .map(|number| (repository.clone(), number))
+        .collect())
+}
 
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("token {}", token))?,
+/// Whether `labels` take in every one of `wanted`, compared like GitHub
+/// does without regard to case.
+fn has_labels(labels: &[github::Label], wanted: &[String]) -> bool {
+    wanted.iter().all(|wanted| {
+        labels
+            .iter()
+            .any(|label| label.name.eq_ignore_ascii_case(wanted))
+    })
+}
+
+/// What discovered PRs must match to be included.
+struct Filters {
+    labels: Vec<String>,
+    authors: Vec<String>,
+}
+
+impl Filters {
+    fn new(selection: &SelectionArgs) -> Self {
+        Filters {
+            labels: selection.labels.clone(),
+            authors: selection.pr_authors.clone(),
+        }
+    }
+
+    /// Whether `pull` passes every filter: it carries all of the labels and
+    /// was opened by one of the authors. GitHub compares label names and
+    /// logins without regard to case, so this does too.
+    fn matches(&self, pull: &github::PullSummary) -> bool {
+        let labelled = has_labels(&pull.labels, &self.labels);
+        let authored = self.authors.is_empty()
+            || pull.user.as_ref().is_some_and(|user| {
+                self.authors
+                    .iter()
+                    .any(|author| user.login.eq_ignore_ascii_case(author))
+            });
+        labelled && authored
+    }
+
+    /// The filters as search qualifiers, each preceded by a space. Search
+    /// requires every `label:` but any one of several `author:`s.
+    fn qualifiers(&self) -> String {
+        let labels = self
+            .labels
+            .iter()
+            .map(|label| format!(" label:\"{}\"", label));
+        let authors = self
+            .authors
+            .iter()
+            .map(|author| format!(" author:{}", author));
+        labels.chain(authors).collect()
+    }
+}
+
+/// The repositories of `org` that pass `--topic` and `--repo-pattern`.
+async fn org_repositories(
+    github: &GithubClient,
+    org: &str,
+    selection: &SelectionArgs,
+) -> Result<Vec<Repository>, Box<dyn std::error::Error>> {
+    let repositories = github.org_repositories(org).await?;
+    let listed = repositories.len();
+    let matching: Vec<Repository> = repositories
+        .into_iter()
+        .filter(|repository| {
+            selection.topics.iter().all(|topic| {
+                repository
+                    .topics
+                    .iter()
+                    .any(|t| t.eq_ignore_ascii_case(topic))
+            })
+        })
+        .filter(|repository| {
+            selection
+                .repo_pattern
+                .as_deref()
+                .is_none_or(|pattern| glob_matches(pattern, &repository.name))
+        })
+        .map(|repository| Repository {
+            owner: org.to_string(),
+            name: repository.name,
+        })
+        .collect();
+    info!(
+        "{} of the {} repositories of {} match",
+        matching.len(),
+        listed,
+        org
     );
-    headers.insert(USER_AGENT, HeaderValue::from_static("rust-client"));
+    Ok(matching)
+}
+
+/// Whether `text` matches `pattern`, where `*` stands for any run of
+/// characters, ignoring case as GitHub does for repository names.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let text = text.to_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Groups PRs for GraphQL queries: each batch is in a single repository
+/// and holds at most [`graphql::BATCH_SIZE`] PRs, in the order given.
+fn batches(targets: &[Target]) -> Vec<(&Repository, Vec<u32>)> {
+    let mut batches: Vec<(&Repository, Vec<u32>)> = Vec::new();
+    for target in targets {
+        match batches.last_mut() {
+            Some((repository, numbers))
+                if **repository == target.repository && numbers.len() < graphql::BATCH_SIZE =>
+            {
+                numbers.push(target.number)
+            }
+            _ => batches.push((&target.repository, vec![target.number])),
+        }
+    }
+    batches
+}
+
+/// How long GitHub is given to work out whether a PR merges cleanly before
+/// it's asked again.
+const MERGEABLE_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// What a command fetches for each PR besides the PR itself, and how it
+/// treats the result.
+#[derive(Default)]
+struct Pipeline {
+    /// Fetch the commits of each PR, which `prs` and the like go without.
+    commits: bool,
+    expand_coauthors: bool,
+    /// Keep the PR descriptions, which are dropped otherwise.
+    include_body: bool,
+    reviews: bool,
+    review_comments: bool,
+    issue_comments: bool,
+    timeline: bool,
+    checks: bool,
+    head_status: bool,
+    fail_on_empty: bool,
+    require_green: bool,
+    fail_on_conflicts: bool,
+    linked_issues: bool,
+    files: bool,
+    commit_files: bool,
+    /// Commits that change none of these are left out.
+    paths: Vec<String>,
+    diffstat: bool,
+    diff: bool,
+    /// With `diff`, the commit to take the diff of instead of the PR's.
+    diff_sha: Option<String>,
+    /// Fetch the patch of each commit, as `patch_dir` does too.
+    patches: bool,
+    /// Where to save the patch of each commit.
+    patch_dir: Option<PathBuf>,
+    /// The branch to look for each commit's change on.
+    verify_landed: Option<String>,
+    /// Collapse the commits several PRs have into the first of them, once
+    /// every PR is in.
+    dedupe: Option<Dedupe>,
+}
+
+impl Pipeline {
+    /// Whether the patch of each commit is fetched.
+    fn fetches_patches(&self) -> bool {
+        self.patches || self.patch_dir.is_some() || self.dedupe == Some(Dedupe::PatchId)
+    }
+
+    /// Turns what was fetched for a PR into what is rendered of it.
+    fn report(
+        &self,
+        repository: &Repository,
+        mut pull: PullRequest,
+        mut commits: Vec<Commit>,
+    ) -> PrReport {
+        // GraphQL knows which issues a PR closes; otherwise the description
+        // is read for them, before it may be dropped.
+        let mut issue_references = Vec::new();
+        let linked_issues = match pull.closing_issues.take() {
+            _ if !self.linked_issues => None,
+            Some(issues) => Some(issues),
+            None => {
+                let body = pull.body.as_deref().unwrap_or_default();
+                issue_references = issues::references(body, repository);
+                Some(Vec::new())
+            }
+        };
+        // GitHub has no body for an empty description; asked for, it is
+        // shown as empty rather than left out.
+        pull.body = self
+            .include_body
+            .then(|| pull.body.take().unwrap_or_default());
+        if self.expand_coauthors {
+            commits = expand_coauthors(commits);
+        }
+        PrReport {
+            repository: repository.clone(),
+            number: pull.number,
+            pull,
+            commits,
+            reviews: None,
+            review_comments: None,
+            issue_comments: None,
+            timeline: None,
+            head_status: None,
+            linked_issues,
+            issue_references,
+            files: None,
+            diffstat: None,
+            diff: None,
+        }
+    }
+
+    /// Fetches what was asked for on top of the PR and its commits.
+    async fn fetch_extras(
+        &self,
+        github: &GithubClient,
+        report: &mut PrReport,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Repository { owner, name } = &report.repository;
+        if self.fail_on_conflicts && report.pull.state == "open" && report.pull.mergeable.is_none()
+        {
+            // The first request only sets GitHub off working out whether
+            // the PR merges; give it a moment and ask again.
+            tokio::time::sleep(MERGEABLE_RETRY_DELAY).await;
+            let pull = github.pull_request(owner, name, report.number).await?;
+            report.pull.mergeable = pull.mergeable;
+            report.pull.mergeable_state = pull.mergeable_state;
+        }
+        if self.reviews {
+            let reviews = github.pull_reviews(owner, name, report.number).await?;
+            report.reviews = Some(reviews::summarize(&reviews));
+        }
+        if self.review_comments {
+            report.review_comments = Some(
+                github
+                    .pull_review_comments(owner, name, report.number)
+                    .await?,
+            );
+        }
+        if self.issue_comments {
+            report.issue_comments = Some(github.issue_comments(owner, name, report.number).await?);
+        }
+        if self.files {
+            report.files = Some(github.pull_files(owner, name, report.number).await?);
+        }
+        if self.diff {
+            report.diff = Some(match &self.diff_sha {
+                Some(sha) => {
+                    let commit = commit_by_prefix(&report.commits, sha)
+                        .map_err(|err| format!("PR #{}: {}", report.number, err))?;
+                    github.commit_diff(owner, name, commit).await?
+                }
+                None => github.pull_diff(owner, name, report.number).await?,
+            });
+        }
+        if self.timeline {
+            let items = github.timeline(owner, name, report.number).await?;
+            report.timeline = Some(timeline::summarize(&items));
+        }
+        if self.checks {
+            // Expanded co-authors share their commit's SHA.
+            let mut statuses: HashMap<String, ci::CheckStatus> = HashMap::new();
+            for commit in &mut report.commits {
+                if !statuses.contains_key(&commit.sha) {
+                    let runs = github.check_runs(owner, name, &commit.sha).await?;
+                    statuses.insert(commit.sha.clone(), ci::summarize(&runs));
+                }
+                commit.checks = statuses.get(&commit.sha).cloned();
+            }
+        }
+        if self.commit_files || self.diffstat || !self.paths.is_empty() {
+            let mut fetched: HashMap<String, github::CommitDetails> = HashMap::new();
+            for commit in &mut report.commits {
+                if !fetched.contains_key(&commit.sha) {
+                    let details = github.commit(owner, name, &commit.sha).await?;
+                    fetched.insert(commit.sha.clone(), details);
+                }
+                let details = &fetched[&commit.sha];
+                commit.files = Some(details.files.clone());
+                commit.stats = self.diffstat.then_some(github::Diffstat {
+                    additions: details.stats.additions,
+                    deletions: details.stats.deletions,
+                    files: details.files.len(),
+                });
+            }
+            if !self.paths.is_empty() {
+                report
+                    .commits
+                    .retain(|commit| paths::touches(commit, &self.paths));
+            }
+            if self.diffstat {
+                report.diffstat = Some(diffstat(&report.commits));
+            }
+            if !self.commit_files {
+                for commit in &mut report.commits {
+                    commit.files = None;
+                }
+            }
+        }
+        if self.fetches_patches() {
+            let mut patches: HashMap<String, String> = HashMap::new();
+            for commit in &mut report.commits {
+                if !patches.contains_key(&commit.sha) {
+                    let patch = github.commit_patch(owner, name, &commit.sha).await?;
+                    patches.insert(commit.sha.clone(), patch);
+                }
+                commit.patch = patches.get(&commit.sha).cloned();
+            }
+        }
+        if let Some(branch) = &self.verify_landed {
+            // A merge commit on the branch carries the PR's changes however
+            // it was merged; before the merge it is only a test merge.
+            let merged_as = match &report.pull.merge_commit_sha {
+                Some(sha)
+                    if report.pull.merged
+                        && landed::contains(github, owner, name, branch, sha).await? =>
+                {
+                    Some(sha.clone())
+                }
+                _ => None,
+            };
+            let mut landings: HashMap<String, landed::Landing> = HashMap::new();
+            for commit in &mut report.commits {
+                if !landings.contains_key(&commit.sha) {
+                    let landing =
+                        if landed::contains(github, owner, name, branch, &commit.sha).await? {
+                            landed::Landing::YES
+                        } else if let Some(sha) = &merged_as {
+                            landed::Landing::squashed(sha)
+                        } else {
+                            landed::Landing::NO
+                        };
+                    landings.insert(commit.sha.clone(), landing);
+                }
+                commit.landed = landings.get(&commit.sha).cloned();
+            }
+        }
+        if self.head_status {
+            let head = &report.pull.head.sha;
+            let contexts = github.commit_statuses(owner, name, head).await?;
+            let runs = github.check_runs(owner, name, head).await?;
+            report.head_status = Some(ci::combine(&contexts, &runs));
+        }
+        for (repository, number) in std::mem::take(&mut report.issue_references) {
+            let issue = match github
+                .issue(&repository.owner, &repository.name, number)
+                .await
+            {
+                Ok(issue) => issue,
+                // A typo in a description shouldn't fail the PR.
+                Err(err)
+                    if err
+                        .downcast_ref::<error::Error>()
+                        .is_some_and(error::Error::is_not_found) =>
+                {
+                    warn!(
+                        "PR #{} closes {}#{}, which doesn't exist",
+                        report.number, repository, number
+                    );
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            report
+                .linked_issues
+                .get_or_insert_with(Vec::new)
+                .push(issues::LinkedIssue {
+                    repository: repository.to_string(),
+                    number: issue.number,
+                    title: issue.title,
+                    state: issue.state,
+                    url: issue.html_url,
+                });
+        }
+        Ok(())
+    }
+
+    /// The requests [`Pipeline::fetch_extras`] makes for a PR.
+    fn extra_urls(&self, api_url: &str, repository: &Repository, number: u32) -> Vec<String> {
+        let Repository { owner, name } = repository;
+        let mut urls = Vec::new();
+        if self.reviews {
+            urls.push(github::pull_reviews_url(api_url, owner, name, number));
+        }
+        if self.review_comments {
+            urls.push(github::pull_review_comments_url(
+                api_url, owner, name, number,
+            ));
+        }
+        if self.issue_comments {
+            urls.push(github::issue_comments_url(api_url, owner, name, number));
+        }
+        if self.files {
+            urls.push(github::pull_files_url(api_url, owner, name, number));
+        }
+        if self.diff {
+            urls.push(match &self.diff_sha {
+                Some(sha) => github::commit_url(api_url, owner, name, sha),
+                None => github::pull_request_url(api_url, owner, name, number),
+            });
+        }
+        if self.timeline {
+            urls.push(github::timeline_url(api_url, owner, name, number));
+        }
+        if self.head_status {
+            // The head commit is only known once the PR is fetched.
+            let head = "{head}";
+            urls.push(github::commit_status_url(api_url, owner, name, head));
+            urls.push(github::check_runs_url(api_url, owner, name, head));
+        }
+        urls
+    }
+}
+
+/// Everything fetched for a single PR.
+struct PrReport {
+    repository: Repository,
+    number: u32,
+    pull: PullRequest,
+    commits: Vec<Commit>,
+    /// Only fetched with `--reviews`.
+    reviews: Option<reviews::ReviewSummary>,
+    /// Only fetched for `comments --review`.
+    review_comments: Option<Vec<github::ReviewComment>>,
+    /// Only fetched for `comments --conversation`.
+    issue_comments: Option<Vec<github::IssueComment>>,
+    /// Only fetched with `--timeline`.
+    timeline: Option<timeline::Timeline>,
+    /// Only fetched with `--head-status`.
+    head_status: Option<ci::CheckStatus>,
+    /// Only fetched with `--linked-issues`.
+    linked_issues: Option<Vec<issues::LinkedIssue>>,
+    /// Issues the description says the PR closes, left for
+    /// [`Pipeline::fetch_extras`] to fetch into `linked_issues`.
+    issue_references: Vec<(Repository, u32)>,
+    /// Only fetched for `files`.
+    files: Option<Vec<github::ChangedFile>>,
+    /// Totals of the commits shown, with `--diffstat`.
+    diffstat: Option<github::Diffstat>,
+    /// Only fetched for `diff`.
+    diff: Option<String>,
+}
+
+/// PRs that couldn't be fetched, and why. A failed GraphQL batch takes all
+/// of its PRs with it.
+struct Failure {
+    prs: Vec<u32>,
+    error: Box<dyn std::error::Error>,
+    /// A number in a PR range that turned out not to be a PR, which
+    /// `--keep-going` passes over in silence.
+    missing_from_range: bool,
+}
+
+impl Failure {
+    /// `pr_url` is where the PR itself is fetched from: only not finding
+    /// it there makes the PR missing, unlike the 404 of, say, the compare
+    /// for a mistyped `--verify-landed` branch.
+    fn new(
+        number: u32,
+        error: Box<dyn std::error::Error>,
+        in_range: bool,
+        pr_url: &str,
+    ) -> Failure {
+        let missing = error
+            .downcast_ref::<error::Error>()
+            .is_some_and(|error| error.is_not_found() && error.url() == Some(pr_url));
+        Failure {
+            prs: vec![number],
+            error,
+            missing_from_range: in_range && missing,
+        }
+    }
+}
+
+/// The one commit whose SHA starts with `prefix`.
+fn commit_by_prefix<'a>(commits: &'a [Commit], prefix: &str) -> Result<&'a str, String> {
+    let mut matching: Vec<&str> = commits
+        .iter()
+        .map(|commit| commit.sha.as_str())
+        .filter(|sha| sha.starts_with(prefix))
+        .collect();
+    // Co-authors are expanded into rows next to each other.
+    matching.dedup();
+    match matching.as_slice() {
+        [sha] => Ok(sha),
+        [] => Err(format!("{} is not one of its commits", prefix)),
+        _ => Err(format!(
+            "{} matches several of its commits: {}",
+            prefix,
+            matching.join(", ")
+        )),
+    }
+}
+
+/// The lines `commits` add and remove between them, and how many files they
+/// change. Expanded co-authors share their commit's SHA and count once.
+fn diffstat(commits: &[Commit]) -> github::Diffstat {
+    let mut shas = std::collections::HashSet::new();
+    let mut files = std::collections::HashSet::new();
+    let mut total = github::Diffstat::default();
+    for commit in commits {
+        if !shas.insert(&commit.sha) {
+            continue;
+        }
+        if let Some(stats) = &commit.stats {
+            total.additions += stats.additions;
+            total.deletions += stats.deletions;
+        }
+        files.extend(commit.files.iter().flatten().map(|file| &file.filename));
+    }
+    total.files = files.len();
+    total
+}
 
-    let response = reqwest::Client::new()
-        .get(&url)
-        .headers(headers)
-        .send()
-        .await?
-        .json::<PullRequest>()
-        .await?;
+/// Adds a copy of each commit attributed to every `Co-authored-by` trailer,
+/// right after the original, so co-authors get rows of their own.
+fn expand_coauthors(commits: Vec<Commit>) -> Vec<Commit> {
+    let mut expanded = Vec::with_capacity(commits.len());
+    for commit in commits {
+        let attributed: Vec<Commit> = trailers::parse(&commit.commit.message)
+            .iter()
+            .filter(|trailer| trailer.is("Co-authored-by"))
+            .map(|trailer| {
+                let mut copy = commit.clone();
+                copy.commit.author.name = trailer.name().to_string();
+                copy
+            })
+            .collect();
+        expanded.push(commit);
+        expanded.extend(attributed);
+    }
+    expanded
+}
 
-    Ok(response.title)
+/// Explains an API failure in terms of the PRs being fetched, rather than
+/// the URL that failed.
+fn explain(
+    err: Box<dyn std::error::Error>,
+    repository: &Repository,
+    pr_numbers: &[u32],
+) -> Box<dyn std::error::Error> {
+    match err.downcast::<error::Error>() {
+        Ok(source) => {
+            let prs: Vec<String> = pr_numbers.iter().map(|pr| format!("#{}", pr)).collect();
+            error::Error::Pr {
+                prs: prs.join(", "),
+                repo: repository.to_string(),
+                source,
+            }
+            .into()
+        }
+        Err(err) => err,
+    }
 }
 
-async fn fetch_commits_for_pr(
-    owner: &str,
-    repo: &str,
+async fn fetch_report(
+    github: &GithubClient,
+    pipeline: &Pipeline,
+    repository: &Repository,
     pr_number: u32,
-    token: &str,
-) -> Result<Vec<Commit>, Box<dyn std::error::Error>> {
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/pulls/{}/commits",
-        owner, repo, pr_number
+) -> Result<PrReport, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let Repository { owner, name } = repository;
+    let fetch = async {
+        let pull = github.pull_request(owner, name, pr_number).await?;
+        let commits = if pipeline.commits {
+            github.pull_commits(owner, name, &pull).await?
+        } else {
+            Vec::new()
+        };
+        Ok::<_, Box<dyn std::error::Error>>((pull, commits))
+    };
+    let (pull, commits) = fetch
+        .await
+        .map_err(|err| explain(err, repository, &[pr_number]))?;
+    info!(
+        "PR #{}: {} commits in {:.2?}",
+        pr_number,
+        commits.len(),
+        started.elapsed()
     );
+    let mut report = pipeline.report(repository, pull, commits);
+    pipeline
+        .fetch_extras(github, &mut report)
+        .await
+        .map_err(|err| explain(err, repository, &[pr_number]))?;
+    Ok(report)
+}
 
-    // Set up headers
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("token {}", token))?,
+/// Fetches a batch of PRs with a single GraphQL query.
+async fn fetch_report_batch(
+    github: &GithubClient,
+    pipeline: &Pipeline,
+    repository: &Repository,
+    pr_numbers: &[u32],
+) -> Result<Vec<(u32, Result<PrReport, Box<dyn std::error::Error>>)>, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let pulls = github
+        .pull_requests_batch(
+            &repository.owner,
+            &repository.name,
+            pr_numbers,
+            pipeline.linked_issues,
+        )
+        .await
+        .map_err(|err| explain(err, repository, pr_numbers))?;
+    for (pull, commits) in pulls.iter().filter_map(|(_, pull)| pull.as_ref().ok()) {
+        info!("PR #{}: {} commits", pull.number, commits.len());
+    }
+    info!(
+        "batch of {} PRs fetched in {:.2?}",
+        pr_numbers.len(),
+        started.elapsed()
     );
-    headers.insert(USER_AGENT, HeaderValue::from_static("rust-client"));
+    let mut reports = Vec::with_capacity(pulls.len());
+    for (number, pull) in pulls {
+        let report = async {
+            let (pull, commits) = pull?;
+            let mut report = pipeline.report(repository, pull, commits);
+            pipeline.fetch_extras(github, &mut report).await?;
+            Ok(report)
+        }
+        .await
+        .map_err(|err| explain(err, repository, &[number]));
+        reports.push((number, report));
+    }
+    Ok(reports)
+}
+
+/// Width the table should fit in: the explicit `--max-width`, otherwise the
+/// terminal width when stdout is a terminal.
+fn table_width(args: &Args) -> Option<usize> {
+    if args.no_truncate {
+        return None;
+    }
+    args.max_width.or_else(|| {
+        terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width as usize)
+    })
+}
 
-    // Make the API request
-    let response = reqwest::Client::new()
-        .get(&url)
-        .headers(headers)
-        .send()
-        .await?
-        .json::<Vec<Commit>>()
-        .await?;
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse_from(with_default_command(std::env::args_os().collect()));
+    let verbose = cli.verbose;
+    let quiet = cli.command.fetch().map_or(0, |fetch| fetch.quiet);
+    let silent = quiet > 1;
+    init_logging(verbose, quiet);
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            if !silent {
+                eprintln!("Error: {}", describe_error(err.as_ref(), verbose));
+            }
+            let code = err
+                .downcast_ref::<error::Error>()
+                .map_or(error::GENERAL_EXIT_CODE, error::Error::exit_code);
+            ExitCode::from(code)
+        }
+    }
+}
 
-    Ok(response)
+/// Lists the PRs that `--keep-going` skipped past, and why.
+fn report_failures(failures: &[Failure], failed: usize, total: usize, verbose: u8) {
+    eprintln!("Failed to fetch {} of {} PRs:", failed, total);
+    for failure in failures {
+        let message = describe_error(failure.error.as_ref(), verbose);
+        // Most errors already say which PR they are about.
+        if failure.error.is::<error::Error>() {
+            eprintln!("  {}", message);
+        } else {
+            let prs: Vec<String> = failure.prs.iter().map(|pr| format!("#{}", pr)).collect();
+            eprintln!("  {}: {}", prs.join(", "), message);
+        }
+    }
 }
 
-fn print_commit_table(pr_number: u32, pr_title: &str, commits: &[Commit]) {
-    println!("PR #{} - {}", pr_number, pr_title);
-    println!(
-        "{:<40} | {:<25} | {:<20} | {}",
-        "Commit SHA", "Date", "Author", "Message"
-    );
-    println!("{:-<40}-+-{:-<25}-+-{:-<60}", "", "", "");
+/// Sends log events to stderr, keeping stdout for the report. `RUST_LOG`
+/// takes precedence over the level picked with `-v`, and `-q` leaves out
+/// warnings, `-qq` everything.
+fn init_logging(verbose: u8, quiet: u8) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        EnvFilter::new(match (verbose, quiet) {
+            (_, 2..) => "off",
+            (_, 1) => "error",
+            (0, _) => "warn",
+            (1, _) => "warn,pr_commits=info",
+            (2, _) => "warn,pr_commits=debug",
+            _ => "trace",
+        })
+    });
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_writer(std::io::stderr)
+        .init();
+}
 
-    for commit in commits {
+/// The message for an error, safe to show: without tokens, and with the
+/// failed request's URL when `--verbose` asks for it.
+fn describe_error(err: &(dyn std::error::Error + 'static), verbose: u8) -> String {
+    let mut message = err.to_string();
+    if verbose > 0 {
+        if let Some(url) = err
+            .downcast_ref::<error::Error>()
+            .and_then(error::Error::url)
+        {
+            message = format!("{} (request: {})", message, url);
+        }
+    }
+    redact::redact(&message)
+}
+
+/// Writes the completion script for `shell` to stdout. It covers every
+/// command and flag, and the values of those taking one of a fixed set such
+/// as `--format`.
+fn completions(shell: clap_complete::Shell) {
+    use clap::CommandFactory;
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+}
+
+/// Writes the man page to stdout, or the pages of every command to `dir`.
+fn man(dir: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    use clap::CommandFactory;
+    let command = Cli::command();
+    match dir {
+        Some(dir) => clap_mangen::generate_to(command, dir)
+            .map_err(|err| format!("can't write the man pages to {}: {}", dir.display(), err))?,
+        None => clap_mangen::Man::new(command).render(&mut std::io::stdout())?,
+    }
+    Ok(())
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let profile = config::load(cli.profile.as_deref())?;
+    match cli.command {
+        Command::Auth { mut action } => {
+            if let AuthCommand::Check { connection, .. } = &mut action {
+                connection.apply(&profile);
+            }
+            auth::run(&action).await
+        }
+        Command::Cache { action } => cache::run(&action),
+        Command::Completions { shell } => {
+            completions(shell);
+            Ok(())
+        }
+        Command::Man { dir } => man(dir.as_deref()),
+        Command::SelfUpdate { check, api_url } => update::run(&api_url, check).await,
+        Command::Commits(args) => commits(args, &profile, cli.verbose).await,
+        Command::Between(mut args) => {
+            args.commits.selection.between = Some((args.base, args.head));
+            commits(args.commits, &profile, cli.verbose).await
+        }
+        Command::Prs(mut args) => {
+            args.connection.apply(&profile);
+            let PrsArgs {
+                selection,
+                format,
+                fetch,
+                connection,
+            } = &args;
+            let pipeline = Pipeline::default();
+            if fetch.dry_run {
+                return dry_run(selection, fetch, connection, &profile, &pipeline);
+            }
+            report(
+                selection,
+                fetch,
+                connection,
+                &profile,
+                &pipeline,
+                cli.verbose,
+                |show_repository, out| {
+                    Ok(prs::renderer(
+                        *format,
+                        show_repository,
+                        fetch.quiet > 0,
+                        out,
+                    ))
+                },
+            )
+            .await
+        }
+        Command::ReleaseNotes(mut args) => {
+            args.connection.apply(&profile);
+            let ReleaseNotesArgs {
+                selection,
+                commits,
+                fetch,
+                connection,
+            } = &args;
+            let pipeline = Pipeline {
+                commits: *commits,
+                ..Pipeline::default()
+            };
+            if fetch.dry_run {
+                return dry_run(selection, fetch, connection, &profile, &pipeline);
+            }
+            report(
+                selection,
+                fetch,
+                connection,
+                &profile,
+                &pipeline,
+                cli.verbose,
+                |show_repository, out| Ok(notes::renderer(show_repository, *commits, out)),
+            )
+            .await
+        }
+        Command::Comments(mut args) => {
+            args.connection.apply(&profile);
+            let CommentsArgs {
+                selection,
+                review,
+                conversation,
+                format,
+                fetch,
+                connection,
+            } = &args;
+            let pipeline = Pipeline {
+                review_comments: *review,
+                issue_comments: *conversation,
+                ..Pipeline::default()
+            };
+            if fetch.dry_run {
+                return dry_run(selection, fetch, connection, &profile, &pipeline);
+            }
+            report(
+                selection,
+                fetch,
+                connection,
+                &profile,
+                &pipeline,
+                cli.verbose,
+                |show_repository, out| {
+                    Ok(comments::renderer(
+                        *format,
+                        show_repository,
+                        fetch.quiet > 0,
+                        out,
+                    ))
+                },
+            )
+            .await
+        }
+        Command::Files(mut args) => {
+            args.connection.apply(&profile);
+            let FilesArgs {
+                selection,
+                format,
+                fetch,
+                connection,
+            } = &args;
+            let pipeline = Pipeline {
+                files: true,
+                ..Pipeline::default()
+            };
+            if fetch.dry_run {
+                return dry_run(selection, fetch, connection, &profile, &pipeline);
+            }
+            report(
+                selection,
+                fetch,
+                connection,
+                &profile,
+                &pipeline,
+                cli.verbose,
+                |show_repository, out| {
+                    Ok(files::renderer(
+                        *format,
+                        show_repository,
+                        fetch.quiet > 0,
+                        out,
+                    ))
+                },
+            )
+            .await
+        }
+        Command::Diff(mut args) => {
+            args.connection.apply(&profile);
+            let DiffArgs {
+                selection,
+                sha,
+                color,
+                fetch,
+                connection,
+            } = &args;
+            // The commit is looked up among the PR's, to check it is one.
+            let pipeline = Pipeline {
+                commits: sha.is_some(),
+                diff: true,
+                diff_sha: sha.clone(),
+                ..Pipeline::default()
+            };
+            if fetch.dry_run {
+                return dry_run(selection, fetch, connection, &profile, &pipeline);
+            }
+            report(
+                selection,
+                fetch,
+                connection,
+                &profile,
+                &pipeline,
+                cli.verbose,
+                |_, out| Ok(diff::renderer(color.enabled(), out)),
+            )
+            .await
+        }
+        Command::Find(mut args) => {
+            args.connection.apply(&profile);
+            args.selection.containing = Some(args.sha.clone());
+            let FindArgs {
+                selection,
+                format,
+                fetch,
+                connection,
+                ..
+            } = &args;
+            let pipeline = Pipeline::default();
+            if fetch.dry_run {
+                return dry_run(selection, fetch, connection, &profile, &pipeline);
+            }
+            report(
+                selection,
+                fetch,
+                connection,
+                &profile,
+                &pipeline,
+                cli.verbose,
+                |show_repository, out| {
+                    Ok(prs::renderer(
+                        *format,
+                        show_repository,
+                        fetch.quiet > 0,
+                        out,
+                    ))
+                },
+            )
+            .await
+        }
+        Command::Compare(mut args) => {
+            args.connection.apply(&profile);
+            let CompareArgs {
+                selection,
+                format,
+                fetch,
+                connection,
+            } = &args;
+            let pipeline = Pipeline {
+                commits: true,
+                patches: true,
+                ..Pipeline::default()
+            };
+            if fetch.dry_run {
+                return dry_run(selection, fetch, connection, &profile, &pipeline);
+            }
+            report(
+                selection,
+                fetch,
+                connection,
+                &profile,
+                &pipeline,
+                cli.verbose,
+                |show_repository, out| {
+                    Ok(compare::renderer(
+                        *format,
+                        show_repository,
+                        fetch.quiet > 0,
+                        out,
+                    ))
+                },
+            )
+            .await
+        }
+        Command::Stack(mut args) => {
+            args.connection.apply(&profile);
+            let StackArgs {
+                selection,
+                format,
+                fetch,
+                connection,
+            } = &args;
+            let pipeline = Pipeline {
+                commits: true,
+                ..Pipeline::default()
+            };
+            if fetch.dry_run {
+                return dry_run(selection, fetch, connection, &profile, &pipeline);
+            }
+            report(
+                selection,
+                fetch,
+                connection,
+                &profile,
+                &pipeline,
+                cli.verbose,
+                |show_repository, out| {
+                    Ok(stack::renderer(
+                        *format,
+                        show_repository,
+                        fetch.quiet > 0,
+                        out,
+                    ))
+                },
+            )
+            .await
+        }
+        Command::Backport(mut args) => {
+            args.connection.apply(&profile);
+            backport(&args, &profile).await
+        }
+    }
+}
+
+/// Lists the commits of the selected PRs, for `commits` and `between`.
+async fn commits(
+    mut args: Args,
+    profile: &config::Profile,
+    verbose: u8,
+) -> Result<(), Box<dyn std::error::Error>> {
+    args.apply(profile)?;
+    // git's output goes straight to the terminal.
+    args.fetch.no_pager |= args.apply;
+    let Args {
+        selection,
+        fetch,
+        connection,
+        ..
+    } = &args;
+    let pipeline = Pipeline {
+        commits: true,
+        expand_coauthors: args.expand_coauthors,
+        include_body: args.include_body,
+        reviews: args.reviews,
+        timeline: args.timeline,
+        checks: args.checks,
+        head_status: args.head_status || args.require_green,
+        require_green: args.require_green,
+        fail_on_conflicts: args.fail_on_conflicts,
+        linked_issues: args.linked_issues,
+        commit_files: args.show_files,
+        paths: args.paths.clone(),
+        diffstat: args.diffstat,
+        patch_dir: args.download_patches.clone(),
+        verify_landed: args.verify_landed.clone(),
+        dedupe: args.dedupe,
+        fail_on_empty: args.fail_on_empty,
+        ..Pipeline::default()
+    };
+    if fetch.dry_run {
+        return dry_run(selection, fetch, connection, profile, &pipeline);
+    }
+    report(
+        selection,
+        fetch,
+        connection,
+        profile,
+        &pipeline,
+        verbose,
+        |show_repository, out| commits_renderer(&args, show_repository, out),
+    )
+    .await
+}
+
+/// Fetches the commits of the selected PRs and backports them, one PR
+/// after the other.
+async fn backport(
+    args: &BackportArgs,
+    profile: &config::Profile,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let requested = args.selection.requested(profile, None)?;
+    let repository = requested[0].repository.clone();
+    if requested
+        .iter()
+        .any(|requested| requested.repository != repository)
+    {
+        return Err("backport takes the PRs of a single repository".into());
+    }
+    let github = args
+        .connection
+        .client(&repository.owner, Some(&repository.name))?;
+    let targets = expand(&github, requested, &Filters::new(&args.selection)).await?;
+    let pipeline = Pipeline {
+        commits: true,
+        ..Pipeline::default()
+    };
+    let mut reports = Vec::new();
+    for target in &targets {
+        reports.push(fetch_report(&github, &pipeline, &target.repository, target.number).await?);
+    }
+    if reports.is_empty() {
+        return Err("no PRs to backport".into());
+    }
+    let branch = args
+        .branch
+        .clone()
+        .unwrap_or_else(|| backport::default_branch(&reports, &args.onto));
+    backport::run(
+        &github,
+        &repository,
+        &reports,
+        &backport::Backport {
+            onto: &args.onto,
+            branch: &branch,
+            remote: &args.remote,
+            open_pr: args.open_pr,
+            dry_run: args.dry_run,
+        },
+    )
+    .await
+}
+
+/// Prints the requests fetching the selected PRs takes, as far as they are
+/// known up front: how many pages of commits a PR has only shows once it's
+/// fetched, and where an open-ended range stops once it's looked up.
+fn dry_run(
+    selection: &SelectionArgs,
+    fetch: &FetchArgs,
+    connection: &ConnectionArgs,
+    profile: &config::Profile,
+    pipeline: &Pipeline,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_url = connection.api_url().trim_end_matches('/');
+    // The repositories of an organization are only known once listed, so
+    // the requests for each are shown once, with a placeholder name.
+    let org = selection.org.as_ref().map(|org| {
+        println!(
+            "GET {} (and its further pages, then the following for each repository)",
+            github::org_repos_url(api_url, org)
+        );
+        vec![Repository {
+            owner: org.clone(),
+            name: "{repo}".to_string(),
+        }]
+    });
+    let requested = selection.requested(profile, org.as_deref())?;
+    let mut targets = Vec::new();
+    let mut lookups = usize::from(org.is_some());
+    let filters = Filters::new(selection);
+    for Requested {
+        repository,
+        selection,
+    } in &requested
+    {
+        let (first, last) = match selection {
+            Selection::One(number) => (*number, *number),
+            Selection::Range(first, last) => (*first, *last),
+            Selection::From(first) => {
+                let url = github::newest_pull_url(api_url, &repository.owner, &repository.name);
+                println!("GET {} (where #{}.. ends)", url, first);
+                lookups += 1;
+                continue;
+            }
+            Selection::Discover(discovery) => {
+                let Repository { owner, name } = repository;
+                match discovery {
+                    Discovery::Open => {
+                        let url = github::open_pulls_url(api_url, owner, name);
+                        println!("GET {} (and its further pages)", url);
+                    }
+                    Discovery::Merged { .. } | Discovery::Search(_) => {
+                        let query = discovery
+                            .query(repository, &filters)
+                            .expect("searches have a query");
+                        let url = github::search_url(api_url, &query);
+                        println!("GET {} (and its further pages)", url);
+                    }
+                    Discovery::Containing(sha) => {
+                        let url = github::commit_pulls_url(api_url, owner, name, sha);
+                        println!("GET {} (and its further pages)", url);
+                    }
+                    Discovery::Between(base, head) => {
+                        let url = github::compare_url(api_url, owner, name, base, head);
+                        println!(
+                            "GET {} (and its further pages, then the PRs of each commit)",
+                            url
+                        );
+                    }
+                    Discovery::Milestone(title) => {
+                        let url = github::milestones_url(api_url, owner, name);
+                        println!(
+                            "GET {} (to find milestone {:?}, then its issues and PRs)",
+                            url, title
+                        );
+                        lookups += 1;
+                    }
+                }
+                lookups += 1;
+                continue;
+            }
+        };
+        targets.extend((first..=last).map(|number| Target {
+            repository: repository.clone(),
+            number,
+            in_range: false,
+        }));
+    }
+    let targets = targets.as_slice();
+    let mut extras = 0;
+    let print_extras = |repository: &Repository, number: u32| {
+        let urls = pipeline.extra_urls(api_url, repository, number);
+        for url in &urls {
+            println!("GET {}", url);
+        }
+        urls.len()
+    };
+    let requests = lookups
+        + match fetch.api {
+            Api::Rest => {
+                for Target {
+                    repository, number, ..
+                } in targets
+                {
+                    let Repository { owner, name } = repository;
+                    println!(
+                        "GET {}",
+                        github::pull_request_url(api_url, owner, name, *number)
+                    );
+                    if pipeline.commits {
+                        println!(
+                            "GET {}",
+                            github::pull_commits_url(api_url, owner, name, *number)
+                        );
+                    }
+                    extras += print_extras(repository, *number);
+                }
+                if pipeline.commits {
+                    println!(
+                        "(plus a request for every 100 commits past the first 100 of a PR; \
+                         PRs over 250 commits go through the compare API)"
+                    );
+                    targets.len() * 2 + extras
+                } else {
+                    targets.len() + extras
+                }
+            }
+            Api::Graphql => {
+                let graphql_url = connection
+                    .graphql_url
+                    .clone()
+                    .unwrap_or_else(|| github::graphql_url(api_url));
+                let batches = batches(targets);
+                for (repository, numbers) in &batches {
+                    let prs: Vec<String> = numbers.iter().map(|pr| format!("#{}", pr)).collect();
+                    println!(
+                        "POST {} ({} PRs {})",
+                        graphql_url,
+                        repository,
+                        prs.join(", ")
+                    );
+                    for number in numbers {
+                        extras += print_extras(repository, *number);
+                    }
+                }
+                println!("(plus a request for every 100 commits past the first 100 of a PR)");
+                batches.len() + extras
+            }
+        };
+    if pipeline.checks {
+        println!("(plus a request for the check runs of each commit)");
+    }
+    if pipeline.commit_files || pipeline.diffstat || !pipeline.paths.is_empty() {
+        println!("(plus a request for the files of each commit)");
+    }
+    if pipeline.fetches_patches() {
+        println!("(plus a request for the patch of each commit)");
+    }
+    if let Some(branch) = &pipeline.verify_landed {
         println!(
-            "{:<40} | {:<25} | {:<20} | {}",
-            commit.sha,
-            commit.commit.author.date,
-            commit.commit.author.name,
-            commit.commit.message.lines().next().unwrap_or("")
+            "(plus a request comparing each commit, and the merge commit of a merged PR, with {})",
+            branch
         );
     }
-    println!("\n");
+    if pipeline.linked_issues && fetch.api == Api::Rest {
+        println!("(plus a request for each issue a PR description says it closes)");
+    }
+    println!(
+        "At least {} request{} for {} PRs{}",
+        requests,
+        if requests == 1 { "" } else { "s" },
+        targets.len(),
+        if lookups > 0 {
+            ", and then the PRs that are looked up"
+        } else {
+            ""
+        }
+    );
+    Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+/// The renderer for `commits`: a Tera template, or one of the built-in
+/// formats.
+fn commits_renderer(
+    args: &Args,
+    show_repository: bool,
+    out: Box<dyn std::io::Write>,
+) -> Result<Box<dyn output::Renderer>, Box<dyn std::error::Error>> {
+    if let Some(path) = &args.template {
+        return output::template_renderer(path, out);
+    }
+    if let Some(branch) = &args.emit_cherry_pick_script {
+        return Ok(cherry_pick::renderer(branch.clone(), args.apply, out));
+    }
+    let mut columns = args.columns.clone();
+    if args.checks && !columns.contains(&Column::Checks) {
+        columns.push(Column::Checks);
+    }
+    if args.verify_landed.is_some() && !columns.contains(&Column::Landed) {
+        columns.push(Column::Landed);
+    }
+    if args.dedupe.is_some() && !columns.contains(&Column::AlsoIn) {
+        columns.push(Column::AlsoIn);
+    }
+    if args.diffstat {
+        for column in [Column::Diffstat, Column::FilesChanged] {
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+    }
+    Ok(output::renderer(
+        args.format.unwrap_or(Format::Table),
+        RenderOptions {
+            columns,
+            table_style: args.table_style,
+            max_width: table_width(args),
+            color: args.color.enabled(),
+            hyperlinks: !args.no_hyperlinks && std::io::stdout().is_terminal(),
+            short_sha: args.short_sha.map(|len| len.unwrap_or(output::SHORT_SHA)),
+            dates: DateDisplay {
+                format: args.date_format.clone(),
+                timezone: args.timezone,
+                relative: args.relative_dates,
+            },
+            full_message: args.full_message,
+            quiet: args.fetch.quiet > 0,
+            show_repository,
+        },
+        out,
+    ))
+}
 
-    // Read the token from the provided file path
-    let token = std::fs::read_to_string(&args.token_path)?
-        .trim()
-        .to_string();
+/// Fetches the selected PRs and writes each one with the renderer made by
+/// `renderer`, which learns whether the PRs span several repositories.
+async fn report(
+    selection: &SelectionArgs,
+    fetch: &FetchArgs,
+    connection: &ConnectionArgs,
+    profile: &config::Profile,
+    pipeline: &Pipeline,
+    verbose: u8,
+    renderer: impl FnOnce(
+        bool,
+        Box<dyn std::io::Write>,
+    ) -> Result<Box<dyn output::Renderer>, Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (github, requested) = match &selection.org {
+        Some(org) => {
+            let github = connection.client(org, None)?;
+            let repositories = org_repositories(&github, org, selection).await?;
+            let requested = selection.requested(profile, Some(&repositories))?;
+            (github, requested)
+        }
+        None => {
+            let requested = selection.requested(profile, None)?;
+            // A GitHub App is looked up on the first repository; it needs
+            // to be installed on all of them.
+            let first = &requested[0].repository;
+            (
+                connection.client(&first.owner, Some(&first.name))?,
+                requested,
+            )
+        }
+    };
+    let targets = expand(&github, requested, &Filters::new(selection)).await?;
+    let targets = targets.as_slice();
 
-    for &pr_number in &args.prs {
-        let pr_title = fetch_pr_title(&args.owner, &args.repo, pr_number, &token).await?;
-        let commits = fetch_commits_for_pr(&args.owner, &args.repo, pr_number, &token).await?;
-        print_commit_table(pr_number, &pr_title, &commits);
+    let silent = fetch.quiet > 1;
+    let pager = if fetch.no_pager || silent {
+        None
+    } else {
+        Pager::spawn()
+    };
+    let (pager, out): (Option<Pager>, Box<dyn std::io::Write>) = match pager {
+        Some((pager, stdin)) => (Some(pager), Box::new(std::io::LineWriter::new(stdin))),
+        None if silent => (None, Box::new(std::io::sink())),
+        None => (None, Box::new(std::io::stdout())),
+    };
+    let show_repository = targets
+        .iter()
+        .any(|target| target.repository != targets[0].repository);
+    let mut renderer = renderer(show_repository, out)?;
+    let progress = progress::bar(targets.len(), pager.is_some() || verbose > 0 || silent);
+    // PRs that failed with --keep-going, with the reason.
+    let mut failures: Vec<Failure> = Vec::new();
+    // PRs without commits, for --fail-on-empty.
+    let mut empty: Vec<u32> = Vec::new();
+    // PRs whose head isn't passing CI, for --require-green.
+    let mut not_green: Vec<u32> = Vec::new();
+    // Open PRs with merge conflicts, for --fail-on-conflicts.
+    let mut conflicting: Vec<u32> = Vec::new();
+    // Selection in PR ranges that aren't PRs.
+    let mut skipped = 0;
+    // Number of the next patch with --download-patches.
+    let mut next_patch = 1;
+    // Every report, with --dedupe, to render once they are all in.
+    let mut held: Vec<PrReport> = Vec::new();
+    let result: Result<(), Box<dyn std::error::Error>> = async {
+        // `buffered` runs up to `concurrency` fetches at once but yields the
+        // results in the order the PRs were requested.
+        let concurrency = fetch.concurrency as usize;
+        let (github, progress) = (&github, &progress);
+        let mut reports: LocalBoxStream<Result<PrReport, Failure>> = match fetch.api {
+            Api::Rest => stream::iter(targets)
+                .map(|target| async move {
+                    progress.set_message(format!("#{}", target.number));
+                    fetch_report(github, pipeline, &target.repository, target.number)
+                        .await
+                        .map_err(|error| {
+                            let Repository { owner, name } = &target.repository;
+                            let url = github::pull_request_url(
+                                github.api_url(),
+                                owner,
+                                name,
+                                target.number,
+                            );
+                            Failure::new(target.number, error, target.in_range, &url)
+                        })
+                })
+                .buffered(concurrency)
+                .boxed_local(),
+            Api::Graphql => stream::iter(batches(targets))
+                .map(|(repository, pr_numbers)| async move {
+                    progress.set_message(format!("#{}", pr_numbers[0]));
+                    let batch = fetch_report_batch(github, pipeline, repository, &pr_numbers)
+                        .await
+                        .map_err(|error| Failure {
+                            missing_from_range: false,
+                            prs: pr_numbers,
+                            error,
+                        });
+                    (repository, batch)
+                })
+                .buffered(concurrency)
+                .flat_map(|(repository, batch)| {
+                    stream::iter(match batch {
+                        Ok(reports) => reports
+                            .into_iter()
+                            .map(|(number, report)| {
+                                report.map_err(|error| {
+                                    let in_range = targets.iter().any(|target| {
+                                        target.repository == *repository
+                                            && target.number == number
+                                            && target.in_range
+                                    });
+                                    // A batch reports missing PRs as the
+                                    // query's.
+                                    Failure::new(number, error, in_range, github.graphql_url())
+                                })
+                            })
+                            .collect(),
+                        Err(failure) => vec![Err(failure)],
+                    })
+                })
+                .boxed_local(),
+        };
+        while let Some(report) = reports.next().await {
+            match report {
+                Ok(report) => {
+                    progress.inc(1);
+                    if !has_labels(&report.pull.labels, &selection.filter_labels) {
+                        info!("skipping #{}: missing a --filter-label", report.number);
+                        continue;
+                    }
+                    if report.commits.is_empty() {
+                        empty.push(report.number);
+                    }
+                    if report
+                        .head_status
+                        .as_ref()
+                        .is_some_and(|status| !status.is_green())
+                    {
+                        not_green.push(report.number);
+                    }
+                    if report.pull.has_conflicts() {
+                        conflicting.push(report.number);
+                    }
+                    if pipeline.dedupe.is_some() {
+                        held.push(report);
+                        continue;
+                    }
+                    if let Some(dir) = &pipeline.patch_dir {
+                        patches::save(dir, &report, &mut next_patch)?;
+                    }
+                    progress.suspend(|| renderer.render_pr(&report))?
+                }
+                Err(failure) if fetch.keep_going => {
+                    progress.inc(failure.prs.len() as u64);
+                    if failure.missing_from_range {
+                        debug!("skipping #{}: not a PR", failure.prs[0]);
+                        skipped += 1;
+                    } else {
+                        failures.push(failure)
+                    }
+                }
+                Err(failure) => return Err(failure.error),
+            }
+        }
+        progress.finish_and_clear();
+        if let Some(by) = pipeline.dedupe {
+            dedupe::collapse(&mut held, by, show_repository);
+            for report in &held {
+                if let Some(dir) = &pipeline.patch_dir {
+                    patches::save(dir, report, &mut next_patch)?;
+                }
+                renderer.render_pr(report)?;
+            }
+        }
+        renderer.finish()
     }
+    .await;
 
-    Ok(())
+    progress.finish_and_clear();
+    // Close the pager's input so it can show the end of the output, then
+    // hand the terminal back only once the user has quit it.
+    drop(renderer);
+    if let Some(pager) = pager {
+        pager.wait()?;
+    }
+
+    if result.is_ok() && !failures.is_empty() {
+        let failed: usize = failures.iter().map(|failure| failure.prs.len()).sum();
+        if !silent {
+            report_failures(&failures, failed, targets.len() - skipped, verbose);
+        }
+        return Err(error::Error::PartialFailure {
+            failed,
+            total: targets.len() - skipped,
+        }
+        .into());
+    }
+
+    if result.is_ok() && pipeline.fail_on_empty && !empty.is_empty() {
+        let prs: Vec<String> = empty.iter().map(|pr| format!("#{}", pr)).collect();
+        return Err(error::Error::Empty {
+            prs: prs.join(", "),
+        }
+        .into());
+    }
+
+    if result.is_ok() && pipeline.require_green && !not_green.is_empty() {
+        let prs: Vec<String> = not_green.iter().map(|pr| format!("#{}", pr)).collect();
+        return Err(error::Error::NotGreen {
+            prs: prs.join(", "),
+        }
+        .into());
+    }
+
+    if result.is_ok() && pipeline.fail_on_conflicts && !conflicting.is_empty() {
+        let prs: Vec<String> = conflicting.iter().map(|pr| format!("#{}", pr)).collect();
+        return Err(error::Error::Conflicts {
+            prs: prs.join(", "),
+        }
+        .into());
+    }
+
+    match result {
+        // The reader (usually the pager) went away before we were done.
+        Err(err)
+            if err
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|err| err.kind() == std::io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_numbers_and_ranges() {
+        assert!(matches!(Selection::parse("12"), Ok(Selection::One(12))));
+        assert!(matches!(
+            Selection::parse("10..15"),
+            Ok(Selection::Range(10, 15))
+        ));
+        assert!(matches!(
+            Selection::parse("7..7"),
+            Ok(Selection::Range(7, 7))
+        ));
+        assert!(matches!(Selection::parse("40.."), Ok(Selection::From(40))));
+    }
+
+    #[test]
+    fn only_a_pr_that_isnt_there_is_missing_from_its_range() {
+        let repository = Repository {
+            owner: "o".to_string(),
+            name: "r".to_string(),
+        };
+        let pr_url = github::pull_request_url(github::API_URL, "o", "r", 121);
+        let not_found =
+            |url: String| explain(error::Error::NotFound { url }.into(), &repository, &[121]);
+        let missing = |error, in_range| Failure::new(121, error, in_range, &pr_url);
+        assert!(missing(not_found(pr_url.clone()), true).missing_from_range);
+        assert!(!missing(not_found(pr_url.clone()), false).missing_from_range);
+        // Such as the compare for a mistyped `--verify-landed` branch.
+        let compare = github::compare_url(github::API_URL, "o", "r", "typo", "abc");
+        assert!(!missing(not_found(compare), true).missing_from_range);
+        assert!(!missing("decoding failed".into(), true).missing_from_range);
+    }
+
+    fn commit(sha: &str) -> Commit {
+        serde_json::from_value(serde_json::json!({
+            "sha": sha,
+            "html_url": "",
+            "commit": {
+                "author": { "name": "Ada", "date": "" },
+                "committer": { "name": "Ada", "date": "" },
+                "message": "Subject"
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn takes_sha_prefixes_of_4_hex_digits_or_more() {
+        assert_eq!(parse_sha_prefix("AbCd12").as_deref(), Ok("abcd12"));
+        assert!(parse_sha_prefix("abc").is_err());
+        assert!(parse_sha_prefix("").is_err());
+        assert!(parse_sha_prefix("main").is_err());
+        assert!(parse_sha_prefix(&"a".repeat(41)).is_err());
+    }
+
+    #[test]
+    fn finds_the_one_commit_a_prefix_matches() {
+        let commits = [commit("abcd01"), commit("abcd01"), commit("abce02")];
+        assert_eq!(commit_by_prefix(&commits, "abcd"), Ok("abcd01"));
+        assert_eq!(
+            commit_by_prefix(&commits, "abc"),
+            Err("abc matches several of its commits: abcd01, abce02".to_string())
+        );
+        assert!(commit_by_prefix(&commits, "ffff").is_err());
+    }
+
+    #[test]
+    fn rejects_empty_ranges() {
+        assert_eq!(
+            Selection::parse("15..10").err().as_deref(),
+            Some("empty PR range \"15..10\"")
+        );
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_number() {
+        for text in ["", "x", "-3", "..5", "1..x", "1...3", "1..2..3"] {
+            assert_eq!(
+                Selection::parse(text).err(),
+                Some(format!("invalid PR number or range {:?}", text)),
+                "{}",
+                text
+            );
+        }
+    }
 }
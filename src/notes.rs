@@ -0,0 +1,57 @@
+use crate::output::{self, Renderer};
+use crate::PrReport;
+use std::error::Error;
+use std::io::Write;
+
+/// Writes release notes as a Markdown list with a line per PR, under a
+/// heading per repository when there are several, and optionally the
+/// subject line of each commit under its PR.
+pub fn renderer(show_repository: bool, commits: bool, out: Box<dyn Write>) -> Box<dyn Renderer> {
+    Box::new(NotesRenderer {
+        out,
+        show_repository,
+        commits,
+        repository: None,
+    })
+}
+
+struct NotesRenderer {
+    out: Box<dyn Write>,
+    show_repository: bool,
+    commits: bool,
+    /// The repository whose heading was written last.
+    repository: Option<String>,
+}
+
+impl Renderer for NotesRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        let repository = report.repository.to_string();
+        if self.show_repository && self.repository.as_ref() != Some(&repository) {
+            if self.repository.is_some() {
+                writeln!(self.out)?;
+            }
+            writeln!(self.out, "## {}\n", repository)?;
+            self.repository = Some(repository);
+        }
+        writeln!(
+            self.out,
+            "- {} ([#{}]({}))",
+            report.pull.title.trim(),
+            report.number,
+            report.pull.html_url
+        )?;
+        if self.commits {
+            for commit in &report.commits {
+                let subject = commit.commit.message.lines().next().unwrap_or("");
+                let sha = output::short_sha(&commit.sha);
+                writeln!(self.out, "  - {} ({})", subject, sha)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
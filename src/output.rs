@@ -0,0 +1,1066 @@
+use crate::ci::CheckStatus;
+use crate::color;
+use crate::dates::DateDisplay;
+use crate::github::{ChangedFile, Commit, Diffstat};
+use crate::issues::LinkedIssue;
+use crate::landed::Landing;
+use crate::reviews::ReviewSummary;
+use crate::table::{Cell, Table, TableStyle};
+use crate::timeline::Timeline;
+use crate::trailers::{self, Trailer};
+use crate::PrReport;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Fixed-width table for reading in a terminal
+    Table,
+    /// A single JSON array with one object per PR
+    Json,
+    /// One row per commit, for spreadsheets
+    Csv,
+    /// GitHub-flavored Markdown table per PR
+    Markdown,
+    /// One YAML document per PR
+    Yaml,
+    /// One JSON object per commit, written as soon as its PR is fetched
+    Ndjson,
+    /// Standalone HTML page with sortable tables
+    Html,
+}
+
+/// A commit field that can be shown in the tabular formats.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    Sha,
+    Date,
+    Author,
+    Message,
+    Committer,
+    CommitterDate,
+    /// Names from `Co-authored-by` trailers
+    CoAuthoredBy,
+    /// Names from `Reviewed-by` trailers
+    ReviewedBy,
+    /// Names from `Signed-off-by` trailers
+    SignedOffBy,
+    /// CI status from the commit's check runs, with --checks
+    Checks,
+    /// Lines added and removed, with --diffstat
+    Diffstat,
+    /// Number of files changed, with --diffstat
+    FilesChanged,
+    /// Whether the commit's change is on the branch, with --verify-landed
+    Landed,
+    /// The other PRs that have the commit, with --dedupe
+    AlsoIn,
+}
+
+impl Column {
+    pub const DEFAULT: [Column; 4] = [Column::Sha, Column::Date, Column::Author, Column::Message];
+
+    /// ANSI style for this column's cells when color is enabled.
+    fn style(self) -> Option<&'static str> {
+        match self {
+            Column::Sha => Some(color::DIM),
+            Column::Date => Some(color::CYAN),
+            Column::Author => Some(color::GREEN),
+            Column::Message => None,
+            Column::Committer => Some(color::GREEN),
+            Column::CommitterDate => Some(color::CYAN),
+            Column::CoAuthoredBy | Column::ReviewedBy | Column::SignedOffBy => Some(color::GREEN),
+            Column::Checks => None,
+            Column::Diffstat | Column::FilesChanged | Column::Landed => None,
+            Column::AlsoIn => None,
+        }
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            Column::Sha => "Commit SHA",
+            Column::Date => "Date",
+            Column::Author => "Author",
+            Column::Message => "Message",
+            Column::Committer => "Committer",
+            Column::CommitterDate => "Committer date",
+            Column::CoAuthoredBy => "Co-authors",
+            Column::ReviewedBy => "Reviewed by",
+            Column::SignedOffBy => "Signed off by",
+            Column::Checks => "Checks",
+            Column::Diffstat => "+/-",
+            Column::FilesChanged => "Files",
+            Column::Landed => "Landed",
+            Column::AlsoIn => "Also in",
+        }
+    }
+
+    /// Name used for the CSV header row.
+    fn key(self) -> &'static str {
+        match self {
+            Column::Sha => "sha",
+            Column::Date => "date",
+            Column::Author => "author",
+            Column::Message => "message",
+            Column::Committer => "committer",
+            Column::CommitterDate => "committer_date",
+            Column::CoAuthoredBy => "co_authored_by",
+            Column::ReviewedBy => "reviewed_by",
+            Column::SignedOffBy => "signed_off_by",
+            Column::Checks => "checks",
+            Column::Diffstat => "diffstat",
+            Column::FilesChanged => "files_changed",
+            Column::Landed => "landed",
+            Column::AlsoIn => "also_in",
+        }
+    }
+
+    fn value<'a>(self, commit: &'a Commit, options: &RenderOptions) -> Cow<'a, str> {
+        match self {
+            Column::Sha => Cow::Borrowed(options.sha(commit)),
+            Column::Date => options.dates.render(&commit.commit.author.date),
+            Column::Author => Cow::Borrowed(&commit.commit.author.name),
+            Column::Message => Cow::Borrowed(first_line(&commit.commit.message)),
+            Column::Committer => Cow::Borrowed(&commit.commit.committer.name),
+            Column::CommitterDate => options.dates.render(&commit.commit.committer.date),
+            Column::CoAuthoredBy => Cow::Owned(trailer_names(commit, "Co-authored-by")),
+            Column::ReviewedBy => Cow::Owned(trailer_names(commit, "Reviewed-by")),
+            Column::SignedOffBy => Cow::Owned(trailer_names(commit, "Signed-off-by")),
+            Column::Checks => Cow::Owned(
+                commit
+                    .checks
+                    .as_ref()
+                    .map(CheckStatus::describe)
+                    .unwrap_or_default(),
+            ),
+            Column::Diffstat => Cow::Owned(
+                commit
+                    .stats
+                    .as_ref()
+                    .map(Diffstat::lines)
+                    .unwrap_or_default(),
+            ),
+            Column::FilesChanged => Cow::Owned(
+                commit
+                    .stats
+                    .as_ref()
+                    .map(|stats| stats.files.to_string())
+                    .unwrap_or_default(),
+            ),
+            Column::Landed => Cow::Owned(
+                commit
+                    .landed
+                    .as_ref()
+                    // Abbreviated even without `--short-sha`, for the
+                    // column to stay narrow.
+                    .map(|landed| landed.describe(options.short_sha.unwrap_or(SHORT_SHA)))
+                    .unwrap_or_default(),
+            ),
+            Column::AlsoIn => Cow::Owned(
+                commit
+                    .also_in
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        }
+    }
+}
+
+/// Comma-separated names from all trailers of the given kind.
+fn trailer_names(commit: &Commit, key: &str) -> String {
+    trailers::parse(&commit.commit.message)
+        .iter()
+        .filter(|trailer| trailer.is(key))
+        .map(|trailer| trailer.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Length `--short-sha` abbreviates SHAs to by default, which the formats
+/// that always abbreviate them use too.
+pub const SHORT_SHA: usize = 8;
+
+/// `sha` abbreviated to [`SHORT_SHA`] characters.
+pub fn short_sha(sha: &str) -> &str {
+    &sha[..SHORT_SHA.min(sha.len())]
+}
+
+/// Settings shared by all renderers.
+pub struct RenderOptions {
+    pub columns: Vec<Column>,
+    pub table_style: TableStyle,
+    /// Width the table is truncated to, or `None` to never truncate.
+    pub max_width: Option<usize>,
+    pub color: bool,
+    /// Emit OSC 8 hyperlinks for SHAs and PR headers.
+    pub hyperlinks: bool,
+    /// Abbreviate SHAs to this many characters in the tabular formats.
+    pub short_sha: Option<usize>,
+    pub dates: DateDisplay,
+    /// Show the whole commit message rather than just its subject line.
+    pub full_message: bool,
+    /// Leave out PR headings, column headers and borders, so the tabular
+    /// formats write nothing but commit rows; Markdown keeps a single
+    /// header for them all to be a table.
+    pub quiet: bool,
+    /// Name the repository along with each PR, for reports spanning several.
+    pub show_repository: bool,
+}
+
+impl RenderOptions {
+    /// `#123`, or `owner/repo#123` when PRs from several repositories are
+    /// shown together.
+    fn pr_label(&self, report: &PrReport) -> String {
+        if self.show_repository {
+            format!("{}#{}", report.repository, report.number)
+        } else {
+            format!("#{}", report.number)
+        }
+    }
+
+    /// The line under a PR's heading: who opened it, which branch goes into
+    /// which, its state, when it was opened and merged, and its size.
+    fn pr_details(&self, report: &PrReport) -> String {
+        let pull = &report.pull;
+        let mut details = format!(
+            "{} · {} ← {} · {} · created {}",
+            pull.author(),
+            pull.base.name,
+            pull.head.name,
+            pull.status(),
+            self.dates.render(&pull.created_at)
+        );
+        if let Some(merged_at) = &pull.merged_at {
+            details.push_str(&format!(" · merged {}", self.dates.render(merged_at)));
+        }
+        let plural = if pull.commits == 1 { "" } else { "s" };
+        details.push_str(&format!(" · {} commit{}", pull.commits, plural));
+        if let Some(total) = &report.diffstat {
+            let plural = if total.files == 1 { "" } else { "s" };
+            details.push_str(&format!(
+                " · {} in {} file{}",
+                total.lines(),
+                total.files,
+                plural
+            ));
+        }
+        if pull.state == "open" {
+            let merge = match (pull.mergeable, pull.mergeable_state.as_deref()) {
+                (Some(false), _) => "conflicts".to_string(),
+                (Some(true), Some(state)) => format!("mergeable ({})", state),
+                (Some(true), None) => "mergeable".to_string(),
+                (None, _) => "mergeability unknown".to_string(),
+            };
+            details.push_str(&format!(" · {}", merge));
+        }
+        if let Some(status) = &report.head_status {
+            let status = match status.state {
+                "none" => "none".to_string(),
+                _ => status.describe(),
+            };
+            details.push_str(&format!(" · CI: {}", status));
+        }
+        if !pull.labels.is_empty() {
+            let labels: Vec<&str> = pull
+                .labels
+                .iter()
+                .map(|label| label.name.as_str())
+                .collect();
+            details.push_str(&format!(" · labels: {}", labels.join(", ")));
+        }
+        details
+    }
+
+    /// `PR #123 - Title`, with drafts marked as such.
+    fn pr_heading(&self, report: &PrReport) -> String {
+        let draft = if report.pull.draft { " [DRAFT]" } else { "" };
+        format!(
+            "PR {}{} - {}",
+            self.pr_label(report),
+            draft,
+            report.pull.title
+        )
+    }
+
+    fn sha<'a>(&self, commit: &'a Commit) -> &'a str {
+        match self.short_sha {
+            Some(len) => &commit.sha[..len.min(commit.sha.len())],
+            None => &commit.sha,
+        }
+    }
+}
+
+/// Writes PRs to the output as they are fetched.
+///
+/// Formats that need to see every PR before writing anything (such as a
+/// single JSON document) buffer in `render_pr` and write in `finish`.
+pub trait Renderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>>;
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+pub fn renderer(
+    format: Format,
+    mut options: RenderOptions,
+    out: Box<dyn Write>,
+) -> Box<dyn Renderer> {
+    match format {
+        Format::Table => Box::new(TableRenderer { out, options }),
+        Format::Json => Box::new(JsonRenderer {
+            out,
+            records: Vec::new(),
+        }),
+        Format::Csv => {
+            // CSV is read by machines, so it keeps absolute timestamps.
+            options.dates.relative = false;
+            Box::new(CsvRenderer {
+                out: csv::Writer::from_writer(out),
+                options,
+                wrote_header: false,
+            })
+        }
+        Format::Markdown => Box::new(MarkdownRenderer {
+            out,
+            options,
+            wrote_header: false,
+        }),
+        Format::Yaml => Box::new(YamlRenderer { out }),
+        Format::Ndjson => Box::new(NdjsonRenderer { out }),
+        Format::Html => Box::new(HtmlRenderer {
+            out,
+            options,
+            started: false,
+        }),
+    }
+}
+
+/// Builds a renderer for a user-supplied Tera template.
+///
+/// The template is rendered once, after every PR has been fetched, with a
+/// `prs` variable holding the same records as the JSON output.
+pub fn template_renderer(
+    path: &Path,
+    out: Box<dyn Write>,
+) -> Result<Box<dyn Renderer>, Box<dyn Error>> {
+    let source = std::fs::read_to_string(path)?;
+    let mut tera = tera::Tera::default();
+    tera.add_raw_template(TEMPLATE_NAME, &source)?;
+    Ok(Box::new(TemplateRenderer {
+        out,
+        tera,
+        records: Vec::new(),
+    }))
+}
+
+/// Flattened, serializable view of a PR used by the structured formats.
+#[derive(Serialize)]
+struct PrRecord {
+    repository: String,
+    number: u32,
+    title: String,
+    url: String,
+    author: String,
+    /// `open`, `merged` or `closed`.
+    state: &'static str,
+    draft: bool,
+    labels: Vec<String>,
+    base: String,
+    head: String,
+    created_at: String,
+    merged: bool,
+    merged_at: Option<String>,
+    mergeable: Option<bool>,
+    mergeable_state: Option<String>,
+    /// Only there with `--include-body`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    commit_count: u32,
+    commits: Vec<CommitRecord>,
+    /// Only there with `--reviews`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reviews: Option<ReviewSummary>,
+    /// Only there with `--timeline`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeline: Option<Timeline>,
+    /// Only there with `--head-status`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    head_status: Option<CheckStatus>,
+    /// Only there with `--linked-issues`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    linked_issues: Option<Vec<LinkedIssue>>,
+    /// Totals of the commits listed, only there with `--diffstat`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diffstat: Option<Diffstat>,
+}
+
+#[derive(Serialize)]
+struct CommitRecord {
+    sha: String,
+    url: String,
+    author: String,
+    date: String,
+    committer: String,
+    committer_date: String,
+    message: String,
+    trailers: Vec<Trailer>,
+    /// Only there with `--checks`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checks: Option<CheckStatus>,
+    /// Only there with `--show-files`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<Vec<ChangedFile>>,
+    /// Only there with `--diffstat`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<Diffstat>,
+    /// Only there with `--verify-landed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    landed: Option<Landing>,
+    /// Only there with `--dedupe`, for commits other PRs have too.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    also_in: Option<Vec<String>>,
+}
+
+impl From<&PrReport> for PrRecord {
+    fn from(report: &PrReport) -> Self {
+        PrRecord {
+            repository: report.repository.to_string(),
+            number: report.number,
+            title: report.pull.title.clone(),
+            url: report.pull.html_url.clone(),
+            author: report.pull.author().to_string(),
+            state: report.pull.status(),
+            draft: report.pull.draft,
+            labels: report
+                .pull
+                .labels
+                .iter()
+                .map(|label| label.name.clone())
+                .collect(),
+            base: report.pull.base.name.clone(),
+            head: report.pull.head.name.clone(),
+            created_at: report.pull.created_at.clone(),
+            merged: report.pull.merged,
+            merged_at: report.pull.merged_at.clone(),
+            mergeable: report.pull.mergeable,
+            mergeable_state: report.pull.mergeable_state.clone(),
+            body: report.pull.body.clone(),
+            commit_count: report.pull.commits,
+            commits: report.commits.iter().map(CommitRecord::from).collect(),
+            reviews: report.reviews.clone(),
+            timeline: report.timeline.clone(),
+            head_status: report.head_status.clone(),
+            linked_issues: report.linked_issues.clone(),
+            diffstat: report.diffstat.clone(),
+        }
+    }
+}
+
+impl From<&Commit> for CommitRecord {
+    fn from(commit: &Commit) -> Self {
+        CommitRecord {
+            sha: commit.sha.clone(),
+            url: commit.html_url.clone(),
+            author: commit.commit.author.name.clone(),
+            date: commit.commit.author.date.clone(),
+            committer: commit.commit.committer.name.clone(),
+            committer_date: commit.commit.committer.date.clone(),
+            message: commit.commit.message.clone(),
+            trailers: trailers::parse(&commit.commit.message),
+            checks: commit.checks.clone(),
+            files: commit.files.clone(),
+            stats: commit.stats.clone(),
+            landed: commit.landed.clone(),
+            also_in: commit.also_in.clone(),
+        }
+    }
+}
+
+/// The issues the PR closes, with `--linked-issues`.
+fn linked_issues(report: &PrReport) -> &[LinkedIssue] {
+    report.linked_issues.as_deref().unwrap_or_default()
+}
+
+/// The PR description when `--include-body` asked for it and there is one.
+fn pr_body(report: &PrReport) -> Option<&str> {
+    let body = report.pull.body.as_deref()?.trim();
+    (!body.is_empty()).then_some(body)
+}
+
+/// The files a commit changes, one line each such as `modified src/main.rs
+/// (+12 -3)`, with `--show-files`.
+fn file_lines(commit: &Commit) -> Vec<String> {
+    commit
+        .files
+        .iter()
+        .flatten()
+        .map(|file| {
+            let name = match &file.previous_filename {
+                Some(previous) => format!("{} → {}", previous, file.filename),
+                None => file.filename.clone(),
+            };
+            format!(
+                "{} {} (+{} -{})",
+                file.status, name, file.additions, file.deletions
+            )
+        })
+        .collect()
+}
+
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or("")
+}
+
+/// Everything after the subject line, without the separating blank lines.
+fn message_body(message: &str) -> Option<&str> {
+    let (_, body) = message.split_once('\n')?;
+    let body = body.trim();
+    (!body.is_empty()).then_some(body)
+}
+
+struct TableRenderer {
+    out: Box<dyn Write>,
+    options: RenderOptions,
+}
+
+impl Renderer for TableRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        let columns = &self.options.columns;
+        let mut table = Table::new(
+            columns
+                .iter()
+                .map(|column| column.header().to_string())
+                .collect(),
+        );
+        for commit in &report.commits {
+            table.add_row(
+                columns
+                    .iter()
+                    .map(|column| {
+                        let cell = Cell::from(column.value(commit, &self.options).as_ref());
+                        match column {
+                            Column::Sha if self.options.hyperlinks => cell.link(&commit.html_url),
+                            _ => cell,
+                        }
+                    })
+                    .collect(),
+            );
+            if self.options.full_message {
+                if let Some(body) = message_body(&commit.commit.message) {
+                    table.add_details(body);
+                }
+            }
+            for line in file_lines(commit) {
+                table.add_details(&line);
+            }
+        }
+
+        if let Some(message) = columns.iter().position(|&column| column == Column::Message) {
+            table.set_flexible(message);
+        }
+        if self.options.quiet {
+            table.hide_headers();
+            return Ok(table.render(
+                &mut self.out,
+                TableStyle::Borderless,
+                self.options.max_width,
+            )?);
+        }
+        if self.options.color {
+            for (i, column) in columns.iter().enumerate() {
+                if let Some(style) = column.style() {
+                    table.set_style(i, style);
+                }
+            }
+        }
+
+        let mut header = self.options.pr_heading(report);
+        if self.options.color {
+            header = color::paint(color::BOLD, &header);
+        }
+        if self.options.hyperlinks {
+            header = color::hyperlink(&report.pull.html_url, &header);
+        }
+        writeln!(self.out, "{}", header)?;
+        let mut details = self.options.pr_details(report);
+        if self.options.color {
+            details = color::paint(color::DIM, &details);
+        }
+        writeln!(self.out, "{}", details)?;
+        for issue in linked_issues(report) {
+            writeln!(
+                self.out,
+                "Closes {} - {} ({})",
+                issue.label(&report.repository),
+                issue.title,
+                issue.state
+            )?;
+        }
+        if let Some(body) = pr_body(report) {
+            writeln!(self.out)?;
+            for line in body.lines() {
+                match line.trim_end() {
+                    "" => writeln!(self.out)?,
+                    line => writeln!(self.out, "    {}", line)?,
+                }
+            }
+            writeln!(self.out)?;
+        }
+        table.render(
+            &mut self.out,
+            self.options.table_style,
+            self.options.max_width,
+        )?;
+        if let Some(reviews) = &report.reviews {
+            writeln!(self.out, "{}", reviews.describe())?;
+        }
+        if let Some(timeline) = &report.timeline {
+            writeln!(self.out, "Timeline:")?;
+            for line in timeline.lines(&self.options.dates) {
+                writeln!(self.out, "  {}", line)?;
+            }
+        }
+        writeln!(self.out, "\n")?;
+        Ok(())
+    }
+}
+
+struct JsonRenderer {
+    out: Box<dyn Write>,
+    records: Vec<PrRecord>,
+}
+
+impl Renderer for JsonRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        self.records.push(PrRecord::from(report));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&mut self.out, &self.records)?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+}
+
+/// A commit together with the PR it belongs to, for the row-oriented formats.
+#[derive(Serialize)]
+struct CommitRow<'a> {
+    pr_repository: String,
+    pr_number: u32,
+    pr_title: &'a str,
+    pr_draft: bool,
+    sha: &'a str,
+    author: &'a str,
+    date: &'a str,
+    committer: &'a str,
+    committer_date: &'a str,
+    message: &'a str,
+    trailers: Vec<Trailer>,
+    /// Only there with `--checks`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checks: Option<&'a CheckStatus>,
+    /// Only there with `--show-files`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<&'a [ChangedFile]>,
+    /// Only there with `--diffstat`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<&'a Diffstat>,
+    /// Only there with `--verify-landed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    landed: Option<&'a Landing>,
+    /// Only there with `--dedupe`, for commits other PRs have too.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    also_in: Option<&'a [String]>,
+}
+
+/// The `csv` writer takes care of quoting fields that contain commas,
+/// quotes or newlines.
+struct CsvRenderer {
+    out: csv::Writer<Box<dyn Write>>,
+    options: RenderOptions,
+    wrote_header: bool,
+}
+
+impl Renderer for CsvRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        let columns = &self.options.columns;
+        if !self.wrote_header && !self.options.quiet {
+            let mut header = vec!["pr_number", "pr_title"];
+            if self.options.show_repository {
+                header.insert(0, "pr_repository");
+            }
+            header.extend(columns.iter().map(|column| column.key()));
+            self.out.write_record(&header)?;
+            self.wrote_header = true;
+        }
+        let number = report.number.to_string();
+        let repository = report.repository.to_string();
+        for commit in &report.commits {
+            let mut record = vec![
+                Cow::Borrowed(number.as_str()),
+                Cow::Borrowed(report.pull.title.as_str()),
+            ];
+            if self.options.show_repository {
+                record.insert(0, Cow::Borrowed(repository.as_str()));
+            }
+            record.extend(columns.iter().map(|column| match column {
+                Column::Message if self.options.full_message => {
+                    Cow::Borrowed(commit.commit.message.as_str())
+                }
+                _ => column.value(commit, &self.options),
+            }));
+            self.out
+                .write_record(record.iter().map(|field| field.as_bytes()))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// With `quiet`, the PRs' rows make a single table, as a Markdown table
+/// can't go without its header.
+struct MarkdownRenderer {
+    out: Box<dyn Write>,
+    options: RenderOptions,
+    wrote_header: bool,
+}
+
+/// Escapes the characters that would otherwise break out of a table cell.
+fn markdown_cell(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+impl Renderer for MarkdownRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        let columns = &self.options.columns;
+        let out = &mut self.out;
+        if !self.options.quiet {
+            writeln!(out, "## {}", self.options.pr_heading(report))?;
+            writeln!(out)?;
+            writeln!(out, "{}", self.options.pr_details(report))?;
+            writeln!(out)?;
+            let issues = linked_issues(report);
+            for issue in issues {
+                writeln!(
+                    out,
+                    "- Closes [{}]({}) - {} ({})",
+                    issue.label(&report.repository),
+                    issue.url,
+                    markdown_cell(&issue.title),
+                    issue.state
+                )?;
+            }
+            if !issues.is_empty() {
+                writeln!(out)?;
+            }
+            if let Some(body) = pr_body(report) {
+                writeln!(out, "{}", body)?;
+                writeln!(out)?;
+            }
+        }
+        if !self.wrote_header {
+            let headers: Vec<&str> = columns.iter().map(|column| column.header()).collect();
+            writeln!(out, "| {} |", headers.join(" | "))?;
+            writeln!(out, "|{}", " --- |".repeat(columns.len()))?;
+            self.wrote_header = self.options.quiet;
+        }
+        for commit in &report.commits {
+            let mut cells: Vec<String> = columns
+                .iter()
+                .map(|column| match column {
+                    Column::Sha => {
+                        format!("[`{}`]({})", self.options.sha(commit), commit.html_url)
+                    }
+                    Column::Message if self.options.full_message => commit
+                        .commit
+                        .message
+                        .trim()
+                        .lines()
+                        .map(markdown_cell)
+                        .collect::<Vec<_>>()
+                        .join("<br>"),
+                    _ => markdown_cell(&column.value(commit, &self.options)),
+                })
+                .collect();
+            // A table row can't be followed by anything but the next row.
+            if let Some(last) = cells.last_mut() {
+                for line in file_lines(commit) {
+                    last.push_str(&format!("<br>{}", markdown_cell(&line)));
+                }
+            }
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+        if self.options.quiet {
+            return Ok(());
+        }
+        writeln!(out)?;
+        if let Some(reviews) = &report.reviews {
+            writeln!(out, "{}", markdown_cell(&reviews.describe()))?;
+            writeln!(out)?;
+        }
+        if let Some(timeline) = &report.timeline {
+            for line in timeline.lines(&self.options.dates) {
+                writeln!(out, "- {}", markdown_cell(&line))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a `---`-separated YAML document per PR, so the stream can be
+/// consumed with multi-document loaders such as `yaml.safe_load_all`.
+struct YamlRenderer {
+    out: Box<dyn Write>,
+}
+
+impl Renderer for YamlRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        writeln!(self.out, "---")?;
+        serde_yaml::to_writer(&mut self.out, &PrRecord::from(report))?;
+        Ok(())
+    }
+}
+
+struct NdjsonRenderer {
+    out: Box<dyn Write>,
+}
+
+impl Renderer for NdjsonRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        for commit in &report.commits {
+            serde_json::to_writer(
+                &mut self.out,
+                &CommitRow {
+                    pr_repository: report.repository.to_string(),
+                    pr_number: report.number,
+                    pr_title: &report.pull.title,
+                    pr_draft: report.pull.draft,
+                    sha: &commit.sha,
+                    author: &commit.commit.author.name,
+                    date: &commit.commit.author.date,
+                    committer: &commit.commit.committer.name,
+                    committer_date: &commit.commit.committer.date,
+                    message: &commit.commit.message,
+                    trailers: trailers::parse(&commit.commit.message),
+                    checks: commit.checks.as_ref(),
+                    files: commit.files.as_deref(),
+                    stats: commit.stats.as_ref(),
+                    landed: commit.landed.as_ref(),
+                    also_in: commit.also_in.as_deref(),
+                },
+            )?;
+            writeln!(self.out)?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+const TEMPLATE_NAME: &str = "template";
+
+struct TemplateRenderer {
+    out: Box<dyn Write>,
+    tera: tera::Tera,
+    records: Vec<PrRecord>,
+}
+
+impl Renderer for TemplateRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        self.records.push(PrRecord::from(report));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut context = tera::Context::new();
+        context.insert("prs", &self.records);
+        self.tera
+            .render_to(TEMPLATE_NAME, &context, &mut self.out)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>PR commits</title>
+<style>
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2rem; color: #1f2328; }
+h2 { border-bottom: 1px solid #d0d7de; padding-bottom: .3rem; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2rem; }
+th, td { border: 1px solid #d0d7de; padding: .4rem .6rem; text-align: left; vertical-align: top; }
+th { background: #f6f8fa; cursor: pointer; user-select: none; }
+th:hover { background: #eaeef2; }
+tr:nth-child(even) td { background: #fafbfc; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, monospace; }
+</style>
+</head>
+<body>
+<h1>PR commits</h1>
+"#;
+
+const HTML_FOOT: &str = r#"<script>
+document.querySelectorAll("th").forEach(function (th) {
+  th.addEventListener("click", function () {
+    var table = th.closest("table");
+    var body = table.tBodies[0];
+    var index = Array.prototype.indexOf.call(th.parentNode.children, th);
+    var ascending = th.dataset.order !== "asc";
+    th.parentNode.querySelectorAll("th").forEach(function (other) { delete other.dataset.order; });
+    th.dataset.order = ascending ? "asc" : "desc";
+    Array.from(body.rows)
+      .sort(function (a, b) {
+        var x = a.cells[index].textContent, y = b.cells[index].textContent;
+        return ascending ? x.localeCompare(y) : y.localeCompare(x);
+      })
+      .forEach(function (row) { body.appendChild(row); });
+  });
+});
+</script>
+</body>
+</html>
+"#;
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+struct HtmlRenderer {
+    out: Box<dyn Write>,
+    options: RenderOptions,
+    started: bool,
+}
+
+impl HtmlRenderer {
+    fn start(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.started {
+            self.out.write_all(HTML_HEAD.as_bytes())?;
+            self.started = true;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        self.start()?;
+        let out = &mut self.out;
+        writeln!(
+            out,
+            "<h2>{}</h2>",
+            html_escape(&self.options.pr_heading(report))
+        )?;
+        writeln!(
+            out,
+            "<p>{}</p>",
+            html_escape(&self.options.pr_details(report))
+        )?;
+        let issues = linked_issues(report);
+        if !issues.is_empty() {
+            writeln!(out, "<ul>")?;
+            for issue in issues {
+                writeln!(
+                    out,
+                    "<li>Closes <a href=\"{}\">{}</a> - {} ({})</li>",
+                    html_escape(&issue.url),
+                    issue.label(&report.repository),
+                    html_escape(&issue.title),
+                    issue.state
+                )?;
+            }
+            writeln!(out, "</ul>")?;
+        }
+        if let Some(body) = pr_body(report) {
+            writeln!(
+                out,
+                "<blockquote>{}</blockquote>",
+                html_escape(body).replace('\n', "<br>")
+            )?;
+        }
+        writeln!(out, "<table>")?;
+        let columns = &self.options.columns;
+        let headers: String = columns
+            .iter()
+            .map(|column| format!("<th>{}</th>", column.header()))
+            .collect();
+        writeln!(out, "<thead><tr>{}</tr></thead>", headers)?;
+        writeln!(out, "<tbody>")?;
+        for commit in &report.commits {
+            let mut cells: Vec<String> = columns
+                .iter()
+                .map(|column| match column {
+                    Column::Sha => format!(
+                        "<a href=\"{}\"><code>{}</code></a>",
+                        html_escape(&commit.html_url),
+                        self.options.sha(commit)
+                    ),
+                    Column::Message if self.options.full_message => {
+                        html_escape(commit.commit.message.trim()).replace('\n', "<br>")
+                    }
+                    _ => html_escape(&column.value(commit, &self.options)),
+                })
+                .collect();
+            // In the commit's own row, for sorting to keep them together.
+            let files = file_lines(commit);
+            if let Some(last) = cells.last_mut().filter(|_| !files.is_empty()) {
+                let items: String = files
+                    .iter()
+                    .map(|line| format!("<li>{}</li>", html_escape(line)))
+                    .collect();
+                last.push_str(&format!("<ul>{}</ul>", items));
+            }
+            let cells: String = cells
+                .iter()
+                .map(|cell| format!("<td>{}</td>", cell))
+                .collect();
+            writeln!(out, "<tr>{}</tr>", cells)?;
+        }
+        writeln!(out, "</tbody>")?;
+        writeln!(out, "</table>")?;
+        if let Some(reviews) = &report.reviews {
+            writeln!(out, "<p>{}</p>", html_escape(&reviews.describe()))?;
+        }
+        if let Some(timeline) = &report.timeline {
+            writeln!(out, "<ul>")?;
+            for line in timeline.lines(&self.options.dates) {
+                writeln!(out, "<li>{}</li>", html_escape(&line))?;
+            }
+            writeln!(out, "</ul>")?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.start()?;
+        self.out.write_all(HTML_FOOT.as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use std::io::IsTerminal;
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+/// A pager process that the report is piped into.
+pub struct Pager {
+    child: Child,
+}
+
+impl Pager {
+    /// Starts `$PAGER` (or `less`) when stdout is a terminal, the same way
+    /// `git log` does. Returns `None` when output should go straight to
+    /// stdout: not a terminal, `PAGER` is empty or `cat`, or the pager
+    /// could not be started.
+    pub fn spawn() -> Option<(Pager, ChildStdin)> {
+        if !std::io::stdout().is_terminal() {
+            return None;
+        }
+        let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        let mut words = pager.split_whitespace();
+        let program = words.next()?;
+        if program == "cat" {
+            return None;
+        }
+
+        let mut command = Command::new(program);
+        command.args(words).stdin(Stdio::piped());
+        if std::env::var_os("LESS").is_none() {
+            // Quit if everything fits on one screen, keep colors and links,
+            // and don't clear the screen on exit.
+            command.env("LESS", "FRX");
+        }
+        let mut child = command.spawn().ok()?;
+        let stdin = child.stdin.take()?;
+        Some((Pager { child }, stdin))
+    }
+
+    /// Waits for the user to quit the pager. The pager's stdin must have
+    /// been dropped first so it sees the end of the output.
+    pub fn wait(mut self) -> std::io::Result<()> {
+        self.child.wait()?;
+        Ok(())
+    }
+}
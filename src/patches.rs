@@ -0,0 +1,50 @@
+use crate::PrReport;
+use std::error::Error;
+use std::path::Path;
+use tracing::info;
+
+/// Longest subject part of a patch file name, as `git format-patch` has it.
+const MAX_NAME_LENGTH: usize = 52;
+
+/// Name of the `number`th patch, such as `0001-Fix-the-parser.patch`, the
+/// way `git format-patch` names them.
+fn file_name(number: usize, subject: &str) -> String {
+    let mut name = String::new();
+    for word in subject
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '_')
+        .filter(|word| !word.is_empty())
+    {
+        if !name.is_empty() {
+            name.push('-');
+        }
+        name.push_str(word);
+    }
+    name.truncate(MAX_NAME_LENGTH);
+    let name = name.trim_matches(|c| c == '-' || c == '.');
+    format!("{:04}-{}.patch", number, name)
+}
+
+/// Writes the patches of a PR's commits into `dir`, numbered on from
+/// `next`, so that several PRs make one series for `git am`.
+pub fn save(dir: &Path, report: &PrReport, next: &mut usize) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(dir)
+        .map_err(|err| format!("can't create {}: {}", dir.display(), err))?;
+    let mut previous: Option<&str> = None;
+    for commit in &report.commits {
+        // Expanded co-authors follow their commit with the same patch.
+        if previous == Some(commit.sha.as_str()) {
+            continue;
+        }
+        previous = Some(&commit.sha);
+        let Some(patch) = &commit.patch else {
+            continue;
+        };
+        let subject = commit.commit.message.lines().next().unwrap_or_default();
+        let path = dir.join(file_name(*next, subject));
+        std::fs::write(&path, patch)
+            .map_err(|err| format!("can't write {}: {}", path.display(), err))?;
+        info!("PR #{}: wrote {}", report.number, path.display());
+        *next += 1;
+    }
+    Ok(())
+}
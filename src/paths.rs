@@ -0,0 +1,94 @@
+use crate::github::Commit;
+
+/// Whether `path` matches `pattern`, compared a directory at a time: `**`
+/// stands for any number of directories, `*` for any run of characters
+/// within one. A pattern also matches everything under the directories it
+/// matches, so `migrations/` and `migrations` both take in
+/// `migrations/0001.sql`.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern
+        .trim_end_matches('/')
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect();
+    let path: Vec<&str> = path.split('/').collect();
+    // Matching a leading run of the path is matching a directory of it.
+    (1..=path.len()).any(|len| matches_parts(&pattern, &path[..len]))
+}
+
+fn matches_parts(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| matches_parts(rest, &path[skip..])),
+        Some((part, rest)) => path
+            .split_first()
+            .is_some_and(|(name, path)| matches_part(part, name) && matches_parts(rest, path)),
+    }
+}
+
+/// Whether the file or directory `name` matches `pattern`, in which `*`
+/// stands for any run of characters.
+fn matches_part(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Whether `commit` changes a file matching any of `patterns`, under its
+/// current name or the one it was renamed from. Its files must have been
+/// fetched.
+pub fn touches(commit: &Commit, patterns: &[String]) -> bool {
+    commit.files.iter().flatten().any(|file| {
+        std::iter::once(&file.filename)
+            .chain(&file.previous_filename)
+            .any(|path| patterns.iter().any(|pattern| matches(pattern, path)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_files_and_directories() {
+        assert!(matches("src/main.rs", "src/main.rs"));
+        assert!(matches("migrations", "migrations/0001.sql"));
+        assert!(matches("migrations/", "migrations/0001.sql"));
+        assert!(!matches("src/main.rs", "src/main.rs.orig"));
+        assert!(!matches("migrations", "db/migrations/0001.sql"));
+    }
+
+    #[test]
+    fn matches_stars_within_a_directory() {
+        assert!(matches("*.md", "README.md"));
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(matches("src/m*n.rs", "src/main.rs"));
+        assert!(matches("src/*a*", "src/main.rs"));
+        assert!(!matches("*.md", "docs/guide.md"));
+        assert!(!matches("src/*.rs", "src/bin/tool.rs"));
+        assert!(!matches("src/mai*ain.rs", "src/main.rs"));
+    }
+
+    #[test]
+    fn matches_double_stars_across_directories() {
+        assert!(matches("**/*.md", "README.md"));
+        assert!(matches("**/*.md", "docs/api/guide.md"));
+        assert!(matches("src/**/mod.rs", "src/mod.rs"));
+        assert!(matches("src/**/mod.rs", "src/a/b/mod.rs"));
+        assert!(matches("**/tests", "crates/core/tests/it.rs"));
+        assert!(!matches("src/**/mod.rs", "lib/a/mod.rs"));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::ratelimit;
+use chrono::{DateTime, Utc};
+use reqwest::Response;
+use std::sync::Mutex;
+
+/// Several tokens whose rate limits are spent together: each request goes
+/// out with whichever token has the most requests left.
+pub struct TokenPool {
+    tokens: Vec<PooledToken>,
+}
+
+struct PooledToken {
+    token: String,
+    /// What the last response sent with this token said about its limit.
+    limit: Mutex<Option<Limit>>,
+}
+
+#[derive(Clone, Copy)]
+struct Limit {
+    remaining: i64,
+    reset: DateTime<Utc>,
+}
+
+impl PooledToken {
+    /// Requests left, counting a token not used yet or whose limit has
+    /// since reset as unlimited.
+    fn available(&self) -> i64 {
+        match *self.limit.lock().unwrap() {
+            Some(limit) if limit.reset > Utc::now() => limit.remaining,
+            _ => i64::MAX,
+        }
+    }
+}
+
+impl TokenPool {
+    pub fn new(tokens: Vec<String>) -> Self {
+        TokenPool {
+            tokens: tokens
+                .into_iter()
+                .map(|token| PooledToken {
+                    token,
+                    limit: Mutex::new(None),
+                })
+                .collect(),
+        }
+    }
+
+    /// The token with the most requests left, along with its index for
+    /// [`TokenPool::record`]. Ties go to the first such token.
+    pub fn pick(&self) -> (usize, &str) {
+        let mut best = 0;
+        let mut best_available = i64::MIN;
+        for (index, token) in self.tokens.iter().enumerate() {
+            let available = token.available();
+            if available > best_available {
+                best = index;
+                best_available = available;
+            }
+        }
+        (best, &self.tokens[best].token)
+    }
+
+    /// Notes the rate limit reported by a response sent with token `index`.
+    pub fn record(&self, index: usize, response: &Response) {
+        if let Some((remaining, reset)) = ratelimit::remaining(response) {
+            *self.tokens[index].limit.lock().unwrap() = Some(Limit { remaining, reset });
+        }
+    }
+
+    /// Whether any token still has requests left.
+    pub fn has_spare(&self) -> bool {
+        self.tokens.iter().any(|token| token.available() > 0)
+    }
+}
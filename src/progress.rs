@@ -0,0 +1,17 @@
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
+
+/// A progress bar on stderr for fetching `total` PRs. It stays hidden when
+/// stderr isn't a terminal, and when it would fight with something else
+/// drawing on it (`hidden`), such as the pager or log output.
+pub fn bar(total: usize, hidden: bool) -> ProgressBar {
+    if hidden || total < 2 || !std::io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::with_draw_target(Some(total as u64), ProgressDrawTarget::stderr());
+    bar.set_style(
+        ProgressStyle::with_template("{bar:30} {pos}/{len} PRs {msg}")
+            .expect("the progress template is valid"),
+    );
+    bar
+}
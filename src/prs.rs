@@ -0,0 +1,148 @@
+use crate::output::Renderer;
+use crate::table::{Cell, Table, TableStyle};
+use crate::PrReport;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::error::Error;
+use std::io::Write;
+
+/// Output formats of the commands that list PRs or their comments.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    /// Fixed-width table for reading in a terminal
+    Table,
+    /// A single JSON array with one object per entry
+    Json,
+    /// One row per entry, for spreadsheets
+    Csv,
+}
+
+/// What `prs` shows of a PR.
+#[derive(Serialize)]
+struct PrSummary {
+    repository: String,
+    number: u32,
+    title: String,
+    draft: bool,
+    commits: u32,
+    url: String,
+}
+
+impl From<&PrReport> for PrSummary {
+    fn from(report: &PrReport) -> Self {
+        PrSummary {
+            repository: report.repository.to_string(),
+            number: report.number,
+            title: report.pull.title.clone(),
+            draft: report.pull.draft,
+            commits: report.pull.commits,
+            url: report.pull.html_url.clone(),
+        }
+    }
+}
+
+/// Lists PRs one per line. The table and JSON are written once every PR is
+/// in, CSV as they come.
+pub fn renderer(
+    format: ListFormat,
+    show_repository: bool,
+    quiet: bool,
+    out: Box<dyn Write>,
+) -> Box<dyn Renderer> {
+    match format {
+        ListFormat::Table => Box::new(TableRenderer {
+            out,
+            show_repository,
+            quiet,
+            table: Table::new(
+                ["PR", "Title", "Commits", "URL"]
+                    .map(str::to_string)
+                    .to_vec(),
+            ),
+        }),
+        ListFormat::Json => Box::new(JsonRenderer {
+            out,
+            prs: Vec::new(),
+        }),
+        ListFormat::Csv => Box::new(CsvRenderer {
+            out: csv::WriterBuilder::new()
+                .has_headers(!quiet)
+                .from_writer(out),
+        }),
+    }
+}
+
+struct TableRenderer {
+    out: Box<dyn Write>,
+    show_repository: bool,
+    quiet: bool,
+    table: Table,
+}
+
+impl Renderer for TableRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        let pr = PrSummary::from(report);
+        let mut label = if self.show_repository {
+            format!("{}#{}", pr.repository, pr.number)
+        } else {
+            format!("#{}", pr.number)
+        };
+        if pr.draft {
+            label.push_str(" [DRAFT]");
+        }
+        self.table.add_row(vec![
+            Cell::from(label.as_str()),
+            Cell::from(pr.title.as_str()),
+            Cell::from(pr.commits.to_string().as_str()),
+            Cell::from(pr.url.as_str()),
+        ]);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let style = if self.quiet {
+            self.table.hide_headers();
+            TableStyle::Borderless
+        } else {
+            TableStyle::Ascii
+        };
+        self.table.render(&mut self.out, style, None)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+struct JsonRenderer {
+    out: Box<dyn Write>,
+    prs: Vec<PrSummary>,
+}
+
+impl Renderer for JsonRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        self.prs.push(PrSummary::from(report));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&mut self.out, &self.prs)?;
+        writeln!(self.out)?;
+        Ok(())
+    }
+}
+
+/// The header row comes from the field names of [`PrSummary`].
+struct CsvRenderer {
+    out: csv::Writer<Box<dyn Write>>,
+}
+
+impl Renderer for CsvRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        self.out.serialize(PrSummary::from(report))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
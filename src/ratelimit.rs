@@ -0,0 +1,68 @@
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Response, StatusCode};
+use std::time::Duration;
+
+/// What to do once the API rate limit is used up.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnRateLimit {
+    /// Sleep until the limit resets, then carry on
+    Wait,
+    /// Stop with an error
+    Fail,
+}
+
+/// If `response` was rejected because the primary rate limit is exhausted,
+/// returns when the limit resets.
+pub fn exhausted(response: &Response) -> Option<DateTime<Utc>> {
+    if !matches!(
+        response.status(),
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+    ) {
+        return None;
+    }
+    match remaining(response)? {
+        (0, reset) => Some(reset),
+        _ => None,
+    }
+}
+
+/// How many requests the token that sent `response` has left, and when its
+/// limit resets, if GitHub said so.
+pub fn remaining(response: &Response) -> Option<(i64, DateTime<Utc>)> {
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<i64>().ok())
+    };
+    let reset = DateTime::from_timestamp(header("x-ratelimit-reset")?, 0)?;
+    Some((header("x-ratelimit-remaining")?, reset))
+}
+
+/// If `response` hit a secondary (abuse detection) rate limit, returns how
+/// long GitHub asked us to wait through `Retry-After`.
+pub fn retry_after(response: &Response) -> Option<Duration> {
+    if !matches!(
+        response.status(),
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+    ) {
+        return None;
+    }
+    let seconds = response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// Formats a reset time for messages, in the user's local time.
+pub fn describe_reset(reset: DateTime<Utc>) -> String {
+    reset.with_timezone(&Local).format("%H:%M:%S").to_string()
+}
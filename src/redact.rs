@@ -0,0 +1,86 @@
+use std::sync::Mutex;
+
+/// Prefixes of the token formats GitHub issues: personal, OAuth, user,
+/// installation and refresh tokens, and fine-grained personal tokens.
+const TOKEN_PREFIXES: [&str; 6] = ["ghp_", "gho_", "ghu_", "ghs_", "ghr_", "github_pat_"];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Every token loaded or minted during this run.
+static SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Tokens shorter than this aren't registered: they could only be test
+/// values, and hiding them would mangle ordinary words.
+const MIN_SECRET_LEN: usize = 8;
+
+/// Remembers `token` so that [`redact`] hides it, whatever its format.
+pub fn register(token: &str) {
+    if token.len() >= MIN_SECRET_LEN {
+        SECRETS.lock().unwrap().push(token.to_string());
+    }
+}
+
+/// Replaces every registered token, and anything shaped like a GitHub token,
+/// in text about to be shown to the user.
+pub fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+    for secret in SECRETS.lock().unwrap().iter() {
+        text = text.replace(secret.as_str(), REDACTED);
+    }
+    for prefix in TOKEN_PREFIXES {
+        let mut redacted = String::with_capacity(text.len());
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find(prefix) {
+            let after = &rest[start + prefix.len()..];
+            let end = after
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(after.len());
+            redacted.push_str(&rest[..start]);
+            // A bare prefix, e.g. in this very list, isn't a token.
+            if end == 0 {
+                redacted.push_str(prefix);
+            } else {
+                redacted.push_str(REDACTED);
+            }
+            rest = &after[end..];
+        }
+        redacted.push_str(rest);
+        text = redacted;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_github_tokens() {
+        assert_eq!(
+            redact("Authorization: token ghp_abc123XYZ failed"),
+            "Authorization: token [REDACTED] failed"
+        );
+        assert_eq!(
+            redact("github_pat_11AB_cd, gho_x."),
+            "[REDACTED], [REDACTED]."
+        );
+    }
+
+    #[test]
+    fn leaves_bare_prefixes() {
+        assert_eq!(
+            redact("tokens start with ghp_ or ghs_"),
+            "tokens start with ghp_ or ghs_"
+        );
+    }
+
+    #[test]
+    fn redacts_registered_tokens_of_any_format() {
+        register("s3cr3t-t0ken-value");
+        register("short");
+        assert_eq!(
+            redact("GET ?token=s3cr3t-t0ken-value: short"),
+            "GET ?token=[REDACTED]: short"
+        );
+    }
+}
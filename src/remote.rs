@@ -0,0 +1,33 @@
+use std::process::{Command, Stdio};
+
+/// The `owner/name` of the repository the current directory is a clone of,
+/// going by the URL of its `origin` remote.
+pub fn origin() -> Option<String> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    repository(String::from_utf8(output.stdout).ok()?.trim())
+}
+
+/// The `owner/name` in a remote URL, in any of the forms GitHub hands out:
+/// `https://github.com/owner/name.git`, `git@github.com:owner/name.git` or
+/// `ssh://git@github.com/owner/name`.
+fn repository(url: &str) -> Option<String> {
+    let path = match url.split_once("://") {
+        // The path starts after the host, and any user or port with it.
+        Some((_, rest)) => rest.split_once('/')?.1,
+        // scp-like syntax: [user@]host:path
+        None => url.split_once(':')?.1,
+    };
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, name) = path.split_once('/')?;
+    (!owner.is_empty() && !name.is_empty() && !name.contains('/'))
+        .then(|| format!("{}/{}", owner, name))
+}
@@ -0,0 +1,80 @@
+use crate::github::{self, Review};
+use serde::Serialize;
+
+/// Where the reviews of a PR stand: each reviewer counts with their latest
+/// approval or request for changes, or as having only commented.
+#[derive(Serialize, Debug, Clone)]
+pub struct ReviewSummary {
+    /// `approved`, `changes_requested` or `review_required`, like GitHub's
+    /// review decision but without the branch protection rules.
+    pub decision: &'static str,
+    pub approved: Vec<String>,
+    pub changes_requested: Vec<String>,
+    pub commented: Vec<String>,
+}
+
+/// Sums up `reviews`, given oldest first.
+pub fn summarize(reviews: &[Review]) -> ReviewSummary {
+    // Reviewers in the order they first reviewed, with their verdict.
+    let mut verdicts: Vec<(&str, &str)> = Vec::new();
+    for review in reviews {
+        let login = github::login(review.user.as_ref());
+        let state = match review.state.as_str() {
+            "PENDING" => continue,
+            // A dismissed approval or request for changes no longer counts.
+            "DISMISSED" => "COMMENTED",
+            state => state,
+        };
+        match verdicts.iter_mut().find(|(reviewer, _)| *reviewer == login) {
+            // Comments after a verdict don't take it back.
+            Some((_, verdict)) if state != "COMMENTED" || review.state == "DISMISSED" => {
+                *verdict = state
+            }
+            Some(_) => {}
+            None => verdicts.push((login, state)),
+        }
+    }
+    let with = |wanted: &str| -> Vec<String> {
+        verdicts
+            .iter()
+            .filter(|(_, verdict)| *verdict == wanted)
+            .map(|(reviewer, _)| reviewer.to_string())
+            .collect()
+    };
+    let (approved, changes_requested) = (with("APPROVED"), with("CHANGES_REQUESTED"));
+    let decision = if !changes_requested.is_empty() {
+        "changes_requested"
+    } else if !approved.is_empty() {
+        "approved"
+    } else {
+        "review_required"
+    };
+    ReviewSummary {
+        decision,
+        approved,
+        changes_requested,
+        commented: with("COMMENTED"),
+    }
+}
+
+impl ReviewSummary {
+    /// One line for the human-readable formats, such as
+    /// `Reviews: changes requested (approved by ada; changes requested by bob)`.
+    pub fn describe(&self) -> String {
+        let groups: Vec<String> = [
+            ("approved by", &self.approved),
+            ("changes requested by", &self.changes_requested),
+            ("commented by", &self.commented),
+        ]
+        .iter()
+        .filter(|(_, reviewers)| !reviewers.is_empty())
+        .map(|(verb, reviewers)| format!("{} {}", verb, reviewers.join(", ")))
+        .collect();
+        let decision = self.decision.replace('_', " ");
+        if groups.is_empty() {
+            format!("Reviews: {}, none yet", decision)
+        } else {
+            format!("Reviews: {} ({})", decision, groups.join("; "))
+        }
+    }
+}
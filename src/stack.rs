@@ -0,0 +1,406 @@
+use crate::output::{self, Renderer};
+use crate::prs::ListFormat;
+use crate::{PrReport, Repository};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::Write;
+
+/// A PR of the stack, with its commits.
+struct Node {
+    repository: Repository,
+    label: String,
+    number: u32,
+    title: String,
+    status: &'static str,
+    base: String,
+    head: String,
+    /// SHA and subject, oldest first.
+    commits: Vec<(String, String)>,
+}
+
+impl Node {
+    fn new(report: &PrReport, label: String) -> Node {
+        Node {
+            repository: report.repository.clone(),
+            label,
+            number: report.number,
+            title: report.pull.title.clone(),
+            status: report.pull.status(),
+            base: report.pull.base.name.clone(),
+            head: report.pull.head.name.clone(),
+            commits: report
+                .commits
+                .iter()
+                .map(|commit| {
+                    let subject = commit.commit.message.lines().next().unwrap_or_default();
+                    (commit.sha.clone(), subject.to_string())
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A PR in merge order: after the PR it is stacked on, with the commits
+/// that PR or one further down already has left out.
+struct Placed {
+    node: usize,
+    parent: Option<usize>,
+    depth: usize,
+    /// Whether it is the last PR stacked on its parent.
+    last: bool,
+    commits: Vec<(String, String)>,
+    left_out: usize,
+}
+
+/// Places every PR after the one whose head branch it is based on, in the
+/// same repository. PRs stacked on none of the others start a tree each;
+/// siblings go by number, as GitHub numbers PRs in the order they are
+/// opened.
+fn place(nodes: &[Node]) -> Vec<Placed> {
+    let parents: Vec<Option<usize>> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            (0..nodes.len()).find(|&j| {
+                j != i && nodes[j].repository == node.repository && nodes[j].head == node.base
+            })
+        })
+        .collect();
+    let children = |parent: Option<usize>| {
+        let mut children: Vec<usize> = (0..nodes.len()).filter(|&i| parents[i] == parent).collect();
+        children.sort_by_key(|&i| nodes[i].number);
+        children
+    };
+    let mut placed = Vec::new();
+    let mut visited = vec![false; nodes.len()];
+    // PRs stacked on each other in a loop have no root; they are placed,
+    // from the first one, once every tree is.
+    let roots = children(None)
+        .into_iter()
+        .chain(0..nodes.len())
+        .collect::<Vec<_>>();
+    for root in roots {
+        if visited[root] {
+            continue;
+        }
+        let mut pending = vec![(root, None, 0, true, HashSet::new())];
+        while let Some((i, parent, depth, last, mut seen)) = pending.pop() {
+            if std::mem::replace(&mut visited[i], true) {
+                continue;
+            }
+            let (commits, repeated): (Vec<_>, Vec<_>) = nodes[i]
+                .commits
+                .iter()
+                .cloned()
+                .partition(|(sha, _)| !seen.contains(sha));
+            seen.extend(commits.iter().map(|(sha, _)| sha.clone()));
+            placed.push(Placed {
+                node: i,
+                parent,
+                depth,
+                last,
+                commits,
+                left_out: repeated.len(),
+            });
+            let children: Vec<usize> = children(Some(i))
+                .into_iter()
+                .filter(|&child| !visited[child])
+                .collect();
+            // Pushed in reverse, for the lowest number to come off first.
+            for (n, &child) in children.iter().enumerate().rev() {
+                let last = n + 1 == children.len();
+                pending.push((child, Some(i), depth + 1, last, seen.clone()));
+            }
+        }
+    }
+    placed
+}
+
+/// What `json` and `csv` write of a PR of the stack.
+#[derive(Serialize)]
+struct StackRecord {
+    repository: String,
+    number: u32,
+    title: String,
+    status: &'static str,
+    base: String,
+    head: String,
+    /// The PR it is stacked on.
+    parent: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commits: Option<Vec<CommitRecord>>,
+    /// Commits a PR further down the stack already has.
+    left_out: usize,
+}
+
+#[derive(Serialize)]
+struct CommitRecord {
+    sha: String,
+    message: String,
+}
+
+/// Collects the PRs, then draws the trees they stack into.
+pub fn renderer(
+    format: ListFormat,
+    show_repository: bool,
+    quiet: bool,
+    out: Box<dyn Write>,
+) -> Box<dyn Renderer> {
+    Box::new(StackRenderer {
+        out,
+        format,
+        show_repository,
+        quiet,
+        nodes: Vec::new(),
+    })
+}
+
+struct StackRenderer {
+    out: Box<dyn Write>,
+    format: ListFormat,
+    show_repository: bool,
+    quiet: bool,
+    nodes: Vec<Node>,
+}
+
+impl StackRenderer {
+    fn write_tree(&mut self, placed: &[Placed]) -> Result<(), Box<dyn Error>> {
+        // Whether the PR at each depth above the current one is the last
+        // of its siblings, in which case its line down ends.
+        let mut ended: Vec<bool> = Vec::new();
+        for (n, entry) in placed.iter().enumerate() {
+            let node = &self.nodes[entry.node];
+            if entry.depth == 0 && n > 0 {
+                writeln!(self.out)?;
+            }
+            ended.truncate(entry.depth);
+            let indent: String = ended
+                .iter()
+                .skip(1)
+                .map(|&ended| if ended { "    " } else { "│   " })
+                .collect();
+            let branch = match (entry.depth, entry.last) {
+                (0, _) => "",
+                (_, true) => "└── ",
+                (_, false) => "├── ",
+            };
+            writeln!(
+                self.out,
+                "{}{}{} {} ({} ← {}, {})",
+                indent, branch, node.label, node.title, node.base, node.head, node.status
+            )?;
+            ended.push(entry.last);
+            let has_children = placed
+                .get(n + 1)
+                .is_some_and(|next| next.parent == Some(entry.node));
+            let indent: String = ended
+                .iter()
+                .skip(1)
+                .map(|&ended| if ended { "    " } else { "│   " })
+                .chain(std::iter::once(if has_children {
+                    "│   "
+                } else {
+                    "    "
+                }))
+                .collect();
+            for (sha, subject) in &entry.commits {
+                writeln!(self.out, "{}{} {}", indent, output::short_sha(sha), subject)?;
+            }
+            if entry.left_out > 0 && !self.quiet {
+                writeln!(
+                    self.out,
+                    "{}({} commit{} of the PRs it is stacked on left out)",
+                    indent,
+                    entry.left_out,
+                    if entry.left_out == 1 { "" } else { "s" }
+                )?;
+            }
+        }
+        if !self.quiet && placed.len() > 1 {
+            let order: Vec<&str> = placed
+                .iter()
+                .map(|entry| self.nodes[entry.node].label.as_str())
+                .collect();
+            writeln!(self.out, "\nMerge order: {}", order.join(", "))?;
+        }
+        Ok(())
+    }
+
+    fn records(&self, placed: Vec<Placed>, with_commits: bool) -> Vec<StackRecord> {
+        placed
+            .into_iter()
+            .map(|entry| {
+                let node = &self.nodes[entry.node];
+                StackRecord {
+                    repository: node.repository.to_string(),
+                    number: node.number,
+                    title: node.title.clone(),
+                    status: node.status,
+                    base: node.base.clone(),
+                    head: node.head.clone(),
+                    parent: entry.parent.map(|parent| self.nodes[parent].number),
+                    commits: with_commits.then(|| {
+                        entry
+                            .commits
+                            .into_iter()
+                            .map(|(sha, message)| CommitRecord { sha, message })
+                            .collect()
+                    }),
+                    left_out: entry.left_out,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Renderer for StackRenderer {
+    fn render_pr(&mut self, report: &PrReport) -> Result<(), Box<dyn Error>> {
+        let label = if self.show_repository {
+            format!("{}#{}", report.repository, report.number)
+        } else {
+            format!("#{}", report.number)
+        };
+        self.nodes.push(Node::new(report, label));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let placed = place(&self.nodes);
+        match self.format {
+            ListFormat::Table => self.write_tree(&placed)?,
+            ListFormat::Json => {
+                let records = self.records(placed, true);
+                serde_json::to_writer_pretty(&mut self.out, &records)?;
+                writeln!(self.out)?;
+            }
+            ListFormat::Csv => {
+                let records = self.records(placed, false);
+                let mut out = csv::WriterBuilder::new()
+                    .has_headers(!self.quiet)
+                    .from_writer(&mut self.out);
+                for record in records {
+                    out.serialize(record)?;
+                }
+                out.flush()?;
+            }
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(number: u32, base: &str, head: &str, commits: &[&str]) -> Node {
+        Node {
+            repository: Repository {
+                owner: "o".to_string(),
+                name: "r".to_string(),
+            },
+            label: format!("#{}", number),
+            number,
+            title: format!("PR {}", number),
+            status: "open",
+            base: base.to_string(),
+            head: head.to_string(),
+            commits: commits
+                .iter()
+                .map(|sha| (sha.to_string(), format!("Commit {}", sha)))
+                .collect(),
+        }
+    }
+
+    /// Each PR placed as its number, its parent's number, depth and whether
+    /// it is the last of its siblings.
+    fn order(nodes: &[Node]) -> Vec<(u32, Option<u32>, usize, bool)> {
+        place(nodes)
+            .iter()
+            .map(|placed| {
+                (
+                    nodes[placed.node].number,
+                    placed.parent.map(|parent| nodes[parent].number),
+                    placed.depth,
+                    placed.last,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn places_prs_after_the_ones_they_are_stacked_on() {
+        let nodes = [
+            node(24, "two", "four", &[]),
+            node(23, "one", "three", &[]),
+            node(22, "one", "two", &[]),
+            node(21, "main", "one", &[]),
+            node(30, "main", "other", &[]),
+        ];
+        assert_eq!(
+            order(&nodes),
+            [
+                (21, None, 0, true),
+                (22, Some(21), 1, false),
+                (24, Some(22), 2, true),
+                (23, Some(21), 1, true),
+                (30, None, 0, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_out_commits_of_the_prs_further_down() {
+        let nodes = [
+            node(21, "main", "one", &["a"]),
+            node(22, "one", "two", &["a", "b"]),
+            node(23, "two", "three", &["a", "b", "c"]),
+        ];
+        let placed = place(&nodes);
+        let commits: Vec<(Vec<&str>, usize)> = placed
+            .iter()
+            .map(|placed| {
+                let shas = placed.commits.iter().map(|(sha, _)| sha.as_str()).collect();
+                (shas, placed.left_out)
+            })
+            .collect();
+        assert_eq!(commits, [(vec!["a"], 0), (vec!["b"], 1), (vec!["c"], 2)]);
+    }
+
+    #[test]
+    fn keeps_siblings_from_leaving_out_each_others_commits() {
+        let nodes = [
+            node(21, "main", "one", &[]),
+            node(22, "one", "two", &["a"]),
+            node(23, "one", "three", &["a"]),
+        ];
+        let left_out: Vec<usize> = place(&nodes).iter().map(|placed| placed.left_out).collect();
+        assert_eq!(left_out, [0, 0, 0]);
+    }
+
+    #[test]
+    fn only_stacks_prs_of_the_same_repository() {
+        let mut nodes = [node(21, "main", "one", &[]), node(22, "one", "two", &[])];
+        nodes[1].repository.name = "elsewhere".to_string();
+        assert_eq!(order(&nodes), [(21, None, 0, true), (22, None, 0, true)]);
+    }
+
+    #[test]
+    fn places_prs_stacked_in_a_loop_once_each() {
+        let nodes = [
+            node(21, "two", "one", &["a"]),
+            node(22, "one", "two", &["a"]),
+            node(30, "main", "other", &[]),
+        ];
+        assert_eq!(
+            order(&nodes),
+            [
+                (30, None, 0, true),
+                (21, None, 0, true),
+                (22, Some(21), 1, true),
+            ]
+        );
+        assert_eq!(place(&nodes)[2].left_out, 1);
+    }
+}
@@ -0,0 +1,357 @@
+use crate::color;
+use clap::ValueEnum;
+use std::io::{self, Write};
+
+/// Border presets for the terminal table.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableStyle {
+    /// `|` between columns and a dashed rule under the header
+    Ascii,
+    /// Full box drawn with Unicode line characters
+    Unicode,
+    /// Columns separated by whitespace only
+    Borderless,
+}
+
+/// A horizontal line, drawn from the left edge through every column.
+struct Rule {
+    left: &'static str,
+    fill: &'static str,
+    cross: &'static str,
+    right: &'static str,
+}
+
+struct Borders {
+    left: &'static str,
+    separator: &'static str,
+    right: &'static str,
+    top: Option<Rule>,
+    header: Option<Rule>,
+    bottom: Option<Rule>,
+}
+
+impl TableStyle {
+    fn borders(self) -> Borders {
+        match self {
+            TableStyle::Ascii => Borders {
+                left: "",
+                separator: " | ",
+                right: "",
+                top: None,
+                header: Some(Rule {
+                    left: "",
+                    fill: "-",
+                    cross: "-+-",
+                    right: "",
+                }),
+                bottom: None,
+            },
+            TableStyle::Unicode => Borders {
+                left: "│ ",
+                separator: " │ ",
+                right: " │",
+                top: Some(Rule {
+                    left: "┌─",
+                    fill: "─",
+                    cross: "─┬─",
+                    right: "─┐",
+                }),
+                header: Some(Rule {
+                    left: "├─",
+                    fill: "─",
+                    cross: "─┼─",
+                    right: "─┤",
+                }),
+                bottom: Some(Rule {
+                    left: "└─",
+                    fill: "─",
+                    cross: "─┴─",
+                    right: "─┘",
+                }),
+            },
+            TableStyle::Borderless => Borders {
+                left: "",
+                separator: "  ",
+                right: "",
+                top: None,
+                header: None,
+                bottom: None,
+            },
+        }
+    }
+}
+
+/// Columns are never shrunk below this many characters when fitting a
+/// table to the terminal.
+const MIN_FLEXIBLE_WIDTH: usize = 10;
+
+/// A body cell: its text, plus an optional URL it links to.
+pub struct Cell {
+    text: String,
+    link: Option<String>,
+}
+
+impl Cell {
+    /// Makes the cell an OSC 8 hyperlink to `url`.
+    pub fn link(mut self, url: &str) -> Self {
+        self.link = Some(url.to_string());
+        self
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell {
+            text: text.to_string(),
+            link: None,
+        }
+    }
+}
+
+/// Indentation of the free-form detail lines written under a row.
+const DETAIL_INDENT: &str = "    ";
+
+struct Row {
+    cells: Vec<Cell>,
+    details: Vec<String>,
+}
+
+/// A table laid out with each column as wide as its widest cell.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Row>,
+    flexible: Option<usize>,
+    styles: Vec<Option<&'static str>>,
+    show_headers: bool,
+}
+
+impl Table {
+    pub fn new(headers: Vec<String>) -> Self {
+        Table {
+            rows: Vec::new(),
+            styles: vec![None; headers.len()],
+            headers,
+            flexible: None,
+            show_headers: true,
+        }
+    }
+
+    /// Leaves out the header row and the rules around it, so only the rows
+    /// are written. The headers no longer count towards column widths.
+    pub fn hide_headers(&mut self) {
+        self.show_headers = false;
+    }
+
+    /// Sets the ANSI style applied to every body cell of `column`.
+    pub fn set_style(&mut self, column: usize, style: &'static str) {
+        self.styles[column] = Some(style);
+    }
+
+    /// Marks the column that gets truncated when the table is wider than
+    /// the `max_width` passed to [`Table::render`]. Defaults to the last one.
+    pub fn set_flexible(&mut self, column: usize) {
+        self.flexible = Some(column);
+    }
+
+    pub fn add_row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(Row {
+            cells,
+            details: Vec::new(),
+        });
+    }
+
+    /// Adds text shown indented under the last row, spanning all columns and
+    /// wrapped to the table's width.
+    pub fn add_details(&mut self, text: &str) {
+        if let Some(row) = self.rows.last_mut() {
+            row.details.extend(text.lines().map(str::to_string));
+        }
+    }
+
+    pub fn render(
+        &self,
+        out: &mut dyn Write,
+        style: TableStyle,
+        max_width: Option<usize>,
+    ) -> io::Result<()> {
+        let borders = style.borders();
+        let mut widths: Vec<usize> = (0..self.headers.len())
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .map(|row| display_width(&row.cells[i].text))
+                    .chain(self.show_headers.then(|| display_width(&self.headers[i])))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let flexible = self
+            .flexible
+            .unwrap_or(self.headers.len().saturating_sub(1));
+        if let (Some(max_width), Some(&current)) = (max_width, widths.get(flexible)) {
+            let chrome = display_width(borders.left)
+                + display_width(borders.right)
+                + display_width(borders.separator) * widths.len().saturating_sub(1);
+            let total = chrome + widths.iter().sum::<usize>();
+            if total > max_width {
+                let excess = total - max_width;
+                widths[flexible] = current
+                    .saturating_sub(excess)
+                    .max(MIN_FLEXIBLE_WIDTH.min(current));
+            }
+        }
+
+        if self.show_headers {
+            if let Some(rule) = &borders.top {
+                write_rule(out, rule, &widths)?;
+            }
+            let headers: Vec<Cell> = self
+                .headers
+                .iter()
+                .map(|header| Cell::from(header.as_str()))
+                .collect();
+            write_row(out, &borders, &headers, &widths, &[])?;
+            if let Some(rule) = &borders.header {
+                write_rule(out, rule, &widths)?;
+            }
+        }
+        let inner_width = widths.iter().sum::<usize>()
+            + display_width(borders.separator) * widths.len().saturating_sub(1);
+        for row in &self.rows {
+            write_row(out, &borders, &row.cells, &widths, &self.styles)?;
+            for line in &row.details {
+                write_details(out, &borders, line, inner_width)?;
+            }
+        }
+        if let Some(rule) = &borders.bottom {
+            write_rule(out, rule, &widths)?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of terminal columns `text` occupies.
+fn display_width(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Cuts `text` down to `width` columns, marking the cut with an ellipsis.
+fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn write_rule(out: &mut dyn Write, rule: &Rule, widths: &[usize]) -> io::Result<()> {
+    let fills: Vec<String> = widths
+        .iter()
+        .map(|&width| rule.fill.repeat(width))
+        .collect();
+    writeln!(out, "{}{}{}", rule.left, fills.join(rule.cross), rule.right)
+}
+
+/// Writes a detail line word-wrapped across the full inner width of the
+/// table, inside its left and right borders.
+fn write_details(
+    out: &mut dyn Write,
+    borders: &Borders,
+    line: &str,
+    inner_width: usize,
+) -> io::Result<()> {
+    let width = inner_width
+        .saturating_sub(DETAIL_INDENT.len())
+        .max(MIN_FLEXIBLE_WIDTH);
+    for wrapped in wrap(line, width) {
+        let text = format!("{}{}", DETAIL_INDENT, wrapped);
+        if borders.right.is_empty() {
+            writeln!(out, "{}{}", borders.left, text.trim_end())?;
+        } else {
+            let padding = inner_width.saturating_sub(display_width(&text));
+            writeln!(
+                out,
+                "{}{}{}{}",
+                borders.left,
+                text,
+                " ".repeat(padding),
+                borders.right
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Greedy word wrap. Words longer than `width` are split; an empty line
+/// stays a single empty line.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        let mut word = word.to_string();
+        while display_width(&word) > width {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            let head: String = word.chars().take(width).collect();
+            word = word.chars().skip(width).collect();
+            lines.push(head);
+        }
+        if word.is_empty() {
+            continue;
+        }
+        if !current.is_empty() && display_width(&current) + 1 + display_width(&word) > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(&word);
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Writes one line of cells. The last cell is only padded when the style
+/// draws a right-hand border, so borderless lines carry no trailing spaces.
+fn write_row(
+    out: &mut dyn Write,
+    borders: &Borders,
+    cells: &[Cell],
+    widths: &[usize],
+    styles: &[Option<&str>],
+) -> io::Result<()> {
+    let last = cells.len().saturating_sub(1);
+    let padded: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(i, (cell, &width))| {
+            let text = truncate(&cell.text, width);
+            let padding = width.saturating_sub(display_width(&text));
+            let mut text = match styles.get(i) {
+                Some(Some(style)) => color::paint(style, &text),
+                _ => text,
+            };
+            if let Some(url) = &cell.link {
+                text = color::hyperlink(url, &text);
+            }
+            if i == last && borders.right.is_empty() {
+                text
+            } else {
+                format!("{}{}", text, " ".repeat(padding))
+            }
+        })
+        .collect();
+    writeln!(
+        out,
+        "{}{}{}",
+        borders.left,
+        padded.join(borders.separator),
+        borders.right
+    )
+}
@@ -0,0 +1,111 @@
+use crate::dates::DateDisplay;
+use crate::github::{self, TimelineItem};
+use serde::Serialize;
+
+/// Timeline events worth showing; the rest (comments, labels, commits and
+/// so on) are shown elsewhere or not at all.
+const EVENTS: [&str; 11] = [
+    "head_ref_force_pushed",
+    "base_ref_changed",
+    "base_ref_force_pushed",
+    "review_requested",
+    "review_request_removed",
+    "reviewed",
+    "ready_for_review",
+    "convert_to_draft",
+    "closed",
+    "reopened",
+    "merged",
+];
+
+/// What happened to a PR, oldest first.
+#[derive(Serialize, Debug, Clone)]
+pub struct Timeline {
+    pub events: Vec<Event>,
+    /// Whether the branch was force-pushed after someone approved the PR,
+    /// so that what was approved may not be what is there now.
+    pub force_pushed_after_approval: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Event {
+    pub event: String,
+    pub actor: String,
+    pub created_at: String,
+    /// Who was asked for a review, or the state a review was left in.
+    pub detail: Option<String>,
+}
+
+/// Picks the events worth showing out of a PR's timeline.
+pub fn summarize(items: &[TimelineItem]) -> Timeline {
+    let mut approved = false;
+    let mut force_pushed_after_approval = false;
+    let mut events = Vec::new();
+    for item in items {
+        let Some(event) = item.event.as_deref().filter(|event| EVENTS.contains(event)) else {
+            continue;
+        };
+        // Reviews are told apart by their state, and a pending one isn't
+        // submitted yet.
+        let state = item.state.as_deref().map(str::to_lowercase);
+        if event == "reviewed" && state.as_deref() == Some("pending") {
+            continue;
+        }
+        match event {
+            "reviewed" if state.as_deref() == Some("approved") => approved = true,
+            "head_ref_force_pushed" if approved => force_pushed_after_approval = true,
+            _ => {}
+        }
+        let detail = match event {
+            "review_requested" | "review_request_removed" => item
+                .requested_reviewer
+                .as_ref()
+                .map(|reviewer| reviewer.login.clone())
+                .or_else(|| item.requested_team.as_ref().map(|team| team.name.clone())),
+            "reviewed" => state,
+            _ => None,
+        };
+        events.push(Event {
+            event: event.to_string(),
+            // Reviews name their author `user` rather than `actor`.
+            actor: github::login(item.actor.as_ref().or(item.user.as_ref())).to_string(),
+            created_at: item
+                .created_at
+                .clone()
+                .or_else(|| item.submitted_at.clone())
+                .unwrap_or_default(),
+            detail,
+        });
+    }
+    Timeline {
+        events,
+        force_pushed_after_approval,
+    }
+}
+
+impl Timeline {
+    /// A line per event for the human-readable formats, ending with a
+    /// warning when the PR was force-pushed after approval.
+    pub fn lines(&self, dates: &DateDisplay) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .events
+            .iter()
+            .map(|event| {
+                let mut line = format!(
+                    "{} {} {}",
+                    dates.render(&event.created_at),
+                    event.actor,
+                    event.event.replace('_', " ")
+                );
+                if let Some(detail) = &event.detail {
+                    line.push_str(&format!(": {}", detail));
+                }
+                line
+            })
+            .collect();
+        if self.force_pushed_after_approval {
+            lines.push("Warning: force-pushed after approval".to_string());
+        }
+        lines
+    }
+}
@@ -0,0 +1,117 @@
+use serde::Serialize;
+
+/// A `Key: value` line from the trailer block at the end of a commit
+/// message, such as `Co-authored-by: Name <email>`.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+impl Trailer {
+    /// Case-insensitive key comparison, as git does.
+    pub fn is(&self, key: &str) -> bool {
+        self.key.eq_ignore_ascii_case(key)
+    }
+
+    /// The value without a trailing `<email>`, e.g. the name of a co-author.
+    pub fn name(&self) -> &str {
+        match self.value.find('<') {
+            Some(start) if self.value.ends_with('>') => self.value[..start].trim(),
+            _ => &self.value,
+        }
+    }
+}
+
+/// Parses the trailer block of a commit message.
+///
+/// Like `git interpret-trailers`, only the last paragraph is considered and
+/// only if it is not the subject; every line in it must be a trailer or an
+/// indented continuation of the previous one.
+pub fn parse(message: &str) -> Vec<Trailer> {
+    let message = message.trim_end();
+    let Some((_, last)) = message.rsplit_once("\n\n") else {
+        return Vec::new();
+    };
+
+    let mut trailers: Vec<Trailer> = Vec::new();
+    for line in last.lines() {
+        if line.starts_with([' ', '\t']) {
+            match trailers.last_mut() {
+                Some(trailer) => {
+                    trailer.value.push(' ');
+                    trailer.value.push_str(line.trim());
+                }
+                None => return Vec::new(),
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return Vec::new();
+        };
+        let key = key.trim_end();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Vec::new();
+        }
+        trailers.push(Trailer {
+            key: key.to_string(),
+            value: value.trim().to_string(),
+        });
+    }
+    trailers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trailer(key: &str, value: &str) -> Trailer {
+        Trailer {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_the_last_paragraph() {
+        let message = "Fix the thing\n\nSome: explanation\n\nCo-authored-by: Carol <carol@x>\nSigned-off-by: Ada <ada@x>\n";
+        assert_eq!(
+            parse(message),
+            [
+                trailer("Co-authored-by", "Carol <carol@x>"),
+                trailer("Signed-off-by", "Ada <ada@x>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_the_subject_alone() {
+        assert_eq!(parse("Fixes: a subject line"), []);
+    }
+
+    #[test]
+    fn needs_every_line_to_be_a_trailer() {
+        assert_eq!(parse("Subject\n\nReviewed-by: Ada\nand some prose"), []);
+        assert_eq!(parse("Subject\n\nNot a key: value"), []);
+        assert_eq!(parse("Subject\n\n  indented: before any trailer"), []);
+    }
+
+    #[test]
+    fn joins_continuation_lines() {
+        assert_eq!(
+            parse("Subject\n\nNote: a value\n  running on\nAcked-by: Ada"),
+            [
+                trailer("Note", "a value running on"),
+                trailer("Acked-by", "Ada"),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_without_the_email() {
+        let trailer = trailer("co-authored-by", "Carol Doe <carol@x>");
+        assert!(trailer.is("Co-Authored-By"));
+        assert_eq!(trailer.name(), "Carol Doe");
+        assert_eq!(self::trailer("Reviewed-by", "Ada").name(), "Ada");
+    }
+}
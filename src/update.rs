@@ -0,0 +1,152 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Where the releases of pr-commits are published.
+const REPOSITORY: &str = "dam5h/pr-commits";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Result<&Asset, Box<dyn Error>> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| format!("release {} has no {}", self.tag_name, name).into())
+    }
+}
+
+/// Name of the prebuilt binary for this platform, such as
+/// `pr-commits-x86_64-linux`. Its checksum is published next to it, with
+/// `.sha256` appended.
+fn asset_name() -> String {
+    format!(
+        "pr-commits-{}-{}{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// The numbers of a version such as `v1.2.3`, for comparing versions.
+fn version_numbers(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+/// Replaces the running executable with the latest release, or with
+/// `check` only says whether there is a newer one.
+pub async fn run(api_url: &str, check: bool) -> Result<(), Box<dyn Error>> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("pr-commits/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let release: Release = client
+        .get(format!(
+            "{}/repos/{}/releases/latest",
+            api_url.trim_end_matches('/'),
+            REPOSITORY
+        ))
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    if version_numbers(&release.tag_name) <= version_numbers(current) {
+        eprintln!("pr-commits {} is up to date", current);
+        return Ok(());
+    }
+    if check {
+        eprintln!(
+            "pr-commits {} is available (this is {})",
+            release.tag_name, current
+        );
+        return Ok(());
+    }
+
+    let name = asset_name();
+    let binary = release.asset(&name)?;
+    let checksum = release.asset(&format!("{}.sha256", name))?;
+    let expected = client
+        .get(&checksum.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    // Checksum files are either the bare digest or `sha256sum` output.
+    let expected = expected
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| format!("{} is empty", checksum.name))?
+        .to_lowercase();
+    eprintln!("Downloading {} {}", name, release.tag_name);
+    let bytes = client
+        .get(&binary.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let actual: String = Sha256::digest(&bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if actual != expected {
+        return Err(format!(
+            "checksum mismatch for {}: expected {}, got {}",
+            name, expected, actual
+        )
+        .into());
+    }
+
+    let exe = std::env::current_exe()
+        .map_err(|err| format!("can't find the running executable: {}", err))?;
+    replace(&exe, &bytes)?;
+    eprintln!("Updated {} to {}", exe.display(), release.tag_name);
+    Ok(())
+}
+
+/// Writes `bytes` next to `exe` and moves it into place, so that a failed
+/// write leaves the old executable working.
+fn replace(exe: &Path, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    let with_extension = |extension: &str| {
+        let mut path = exe.as_os_str().to_owned();
+        path.push(extension);
+        PathBuf::from(path)
+    };
+    let new = with_extension(".new");
+    std::fs::write(&new, bytes).map_err(|err| format!("can't write {}: {}", new.display(), err))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))?;
+    }
+    // Windows won't overwrite a running executable, but lets it be renamed.
+    #[cfg(windows)]
+    {
+        let old = with_extension(".old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old)
+            .map_err(|err| format!("can't move {} aside: {}", exe.display(), err))?;
+    }
+    std::fs::rename(&new, exe)
+        .map_err(|err| format!("can't replace {}: {}", exe.display(), err))?;
+    Ok(())
+}